@@ -1,4 +1,6 @@
 pub type UnsignedValuePrecision = u64;
 pub type SignedValuePrecision = i64;
+// fits any whole number of any word size, signed or unsigned
+pub type WideValuePrecision = i128;
 
 pub type FloatingPointPrecison = f64;
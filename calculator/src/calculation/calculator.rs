@@ -1,19 +1,22 @@
-use std::{
-    collections::HashSet,
-    f64::consts::{E, PI},
-    fmt::Debug,
-    ops::Mul,
-};
+use std::{fmt::Debug, rc::Rc};
 
 use crate::{
-    input_parsing::erasable_cluster::ErasableCluster,
-    shared::{errors::ParsingError, sign::Sign},
+    input_parsing::{erasable::Erasable, erasable_cluster::ErasableCluster},
+    shared::{
+        errors::{CalculationError, ParsingError},
+        sign::Sign,
+    },
 };
 
 use super::{
-    calculation_precision::{FloatingPointPrecison, UnsignedValuePrecision},
-    inexact::{expression_to_inexact, Inexact},
-    parsers::parse_into_expression,
+    calculation_precision::{FloatingPointPrecison, UnsignedValuePrecision, WideValuePrecision},
+    environment::{Environment, UserFunction, Value, Variable},
+    exact::{expression_to_exact, Exact},
+    formatting::NumberFormat,
+    inexact::expression_to_inexact,
+    integer::expression_to_integer,
+    parsers::parse_into_statement,
+    units::Unit,
     CalculationResult,
 };
 
@@ -22,6 +25,29 @@ use strum_macros::EnumIter;
 
 pub(super) type Expression = Vec<Term>;
 
+// what a line of input parses into
+#[derive(Debug)]
+pub(super) enum Statement {
+    Expression(Expression),
+    // eg: x = 2
+    Assignment {
+        variable: Variable,
+        expression: Expression,
+    },
+    // eg: f(x) = x^2 + 1
+    FunctionDefinition {
+        name: Variable,
+        function: Rc<UserFunction>,
+    },
+    // eg: 5 km/h -> m/s
+    Conversion {
+        expression: Expression,
+        units: Expression,
+        // as typed, eg: m/s
+        units_name: String,
+    },
+}
+
 #[derive(Debug)]
 pub(crate) struct Term {
     pub(super) fragments: Vec<TermFragment>,
@@ -35,11 +61,156 @@ pub(super) struct TermFragment {
     pub(super) angle_unit: Option<AngleUnit>,
 }
 
+/// The units angles can be in. Numbers without a unit are taken to be in
+/// the session's angle unit wherever an angle is needed, eg: `sin(30)` in
+/// degrees, or `30deg + 15`.
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
-pub(crate) enum AngleUnit {
+pub enum AngleUnit {
     Degrees,
     #[default]
     Radians,
+    Gradians,
+    Turns,
+}
+
+impl AngleUnit {
+    /// The next unit the session's angle unit can be switched to.
+    pub fn next(self) -> Self {
+        match self {
+            AngleUnit::Radians => AngleUnit::Degrees,
+            AngleUnit::Degrees => AngleUnit::Gradians,
+            AngleUnit::Gradians => AngleUnit::Turns,
+            AngleUnit::Turns => AngleUnit::Radians,
+        }
+    }
+
+    pub(crate) fn to_radians(self, angle: FloatingPointPrecison) -> FloatingPointPrecison {
+        match self {
+            AngleUnit::Degrees => angle.to_radians(),
+            AngleUnit::Radians => angle,
+            AngleUnit::Gradians => angle * std::f64::consts::PI / 200.0,
+            AngleUnit::Turns => angle * std::f64::consts::TAU,
+        }
+    }
+
+    pub(crate) fn convert_radians(self, radians: FloatingPointPrecison) -> FloatingPointPrecison {
+        match self {
+            AngleUnit::Degrees => radians.to_degrees(),
+            AngleUnit::Radians => radians,
+            AngleUnit::Gradians => radians * 200.0 / std::f64::consts::PI,
+            AngleUnit::Turns => radians / std::f64::consts::TAU,
+        }
+    }
+
+    pub(crate) fn symbol(self) -> &'static str {
+        match self {
+            AngleUnit::Degrees => Erasable::Degrees.into(),
+            AngleUnit::Radians => Erasable::Radians.into(),
+            AngleUnit::Gradians => Erasable::Gradians.into(),
+            AngleUnit::Turns => Erasable::Turns.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for AngleUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            AngleUnit::Degrees => "degrees",
+            AngleUnit::Radians => "radians",
+            AngleUnit::Gradians => "gradians",
+            AngleUnit::Turns => "turns",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// The size of whole numbers in programmer mode, which wrap around (in two's
+/// complement) when they don't fit, eg: 255 + 1 is 0 as a u8, and 127 + 1 is
+/// -128 as an i8.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WordSize {
+    bits: u32,
+    signed: bool,
+}
+
+impl WordSize {
+    // the order the session's word size is switched through
+    const ALL: [WordSize; 8] = [
+        WordSize::new(8, true),
+        WordSize::new(8, false),
+        WordSize::new(16, true),
+        WordSize::new(16, false),
+        WordSize::new(32, true),
+        WordSize::new(32, false),
+        WordSize::new(64, true),
+        WordSize::new(64, false),
+    ];
+
+    /// What bitwise operators use outside programmer mode.
+    pub(crate) const DEFAULT: WordSize = WordSize::new(64, true);
+
+    const fn new(bits: u32, signed: bool) -> Self {
+        Self { bits, signed }
+    }
+
+    /// The next word size the session can be switched to, where None is
+    /// programmer mode being off.
+    pub fn next(word_size: Option<Self>) -> Option<Self> {
+        match word_size {
+            None => Some(Self::ALL[0]),
+            Some(word_size) => Self::ALL
+                .iter()
+                .skip_while(|other| **other != word_size)
+                .nth(1)
+                .copied(),
+        }
+    }
+
+    // the value with the bits that don't fit dropped, eg: 256 is 0 as a u8
+    pub(crate) fn wrap(self, value: WideValuePrecision) -> WideValuePrecision {
+        let bits = value & self.mask();
+        let is_negative = self.signed && bits >> (self.bits - 1) == 1;
+
+        if is_negative {
+            bits - (1 << self.bits)
+        } else {
+            bits
+        }
+    }
+
+    /// The bits of the value as they're stored, eg: 255 for -1 as an i8.
+    pub(crate) fn bits_of(self, value: WideValuePrecision) -> WideValuePrecision {
+        value & self.mask()
+    }
+
+    pub(crate) fn bits(self) -> u32 {
+        self.bits
+    }
+
+    fn mask(self) -> WideValuePrecision {
+        (1 << self.bits) - 1
+    }
+}
+
+// eg: i8 or u64
+impl std::fmt::Display for WordSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let signedness = if self.signed { 'i' } else { 'u' };
+
+        f.write_str(&format!("{signedness}{}", self.bits))
+    }
+}
+
+impl std::str::FromStr for WordSize {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|word_size| word_size.to_string() == s)
+            .ok_or(())
+    }
 }
 
 #[derive(Debug)]
@@ -51,6 +222,11 @@ pub(super) enum TermFragmentMagnitude {
         constant: NamedConstant,
     },
     Function(Function),
+    Variable(Variable),
+    // one of the unit, eg: the h of km/h
+    Unit(Unit),
+    // worked out with whole numbers, eg: 6 and 3
+    Bitwise(BitwiseOperation),
     // Inexact(FloatingPointPrecison),
 }
 
@@ -58,16 +234,19 @@ pub(super) enum TermFragmentMagnitude {
 pub(super) enum NamedConstant {
     Pi,
     E,
-}
-
-enum Angle {
-    Degrees(Expression),
-    Radians(Expression),
+    // a previous result; None is the latest one, Some(n) is the nth one
+    Answer(Option<usize>),
 }
 
 #[derive(Debug)]
 pub(super) enum UnnamedConstant {
     Integer(UnsignedValuePrecision),
+    // eg: 0x1F, 0b1011 and 0o17
+    Hexadecimal(UnsignedValuePrecision),
+    Binary(UnsignedValuePrecision),
+    Octal(UnsignedValuePrecision),
+    // built from Erasable::FractionDivider, which isn't parsed yet
+    #[allow(dead_code)]
     Fraction {
         numerator: Expression,
         denominator: Expression,
@@ -82,6 +261,16 @@ pub(super) enum UnnamedConstant {
     },
 }
 
+#[derive(Debug)]
+pub(super) enum BitwiseOperation {
+    And(Expression, Expression),
+    Or(Expression, Expression),
+    Xor(Expression, Expression),
+    ShiftLeft(Expression, Expression),
+    ShiftRight(Expression, Expression),
+    Not(Expression),
+}
+
 // used for calculations
 #[derive(Debug)]
 pub(super) enum Function {
@@ -94,6 +283,11 @@ pub(super) enum Function {
     Arctan(Expression),
     // in the form NthRoot(n, value under the root)
     NthRoot(Expression, Expression),
+    // defined during the session; eg: f(3) after f(x) = x^2 + 1
+    User {
+        name: Variable,
+        arguments: Vec<Expression>,
+    },
 }
 
 #[derive(Default, Clone, Copy, Debug)]
@@ -106,25 +300,135 @@ pub(super) enum MultipliedOrDivided {
     Neither,
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(EnumIter)]
 enum InexactOutputMode {
     InexactRadians,
     InexactDegrees,
+    InexactGradians,
+    InexactTurns,
+    InexactDegreesMinutesSeconds,
 }
 
 #[derive(EnumIter)]
 enum ExactOutputMode {
-    ExactImproperFractionRadians,
-    ExactImproperFractionDegrees,
-    ExactMixedFractionRadians,
-    ExactMixedFractionDegrees,
+    ImproperFractionRadians,
+    ImproperFractionDegrees,
+    MixedFractionRadians,
+    MixedFractionDegrees,
+}
+
+/// The kinds of output a result can be shown in.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum OutputMode {
+    /// eg: 7/2
+    #[default]
+    Fraction,
+    /// eg: 3 1/2
+    MixedFraction,
+    /// eg: 3.5
+    Decimal,
+    /// eg: 12°30'15", for angles
+    DegreesMinutesSeconds,
+    /// eg: 0x1F, for whole numbers
+    Hexadecimal,
+    /// eg: 0b1011, for whole numbers
+    Binary,
+    /// eg: 0o17, for whole numbers
+    Octal,
+}
+
+/// How results are shown. Angles are shown in the session's angle unit
+/// first.
+#[derive(Clone, Debug, Default)]
+pub struct OutputSettings {
+    /// The output mode shown first, before the others.
+    pub output_mode: OutputMode,
+    /// How decimals are written, eg: rounded or in scientific notation.
+    pub number_format: NumberFormat,
+}
+
+impl OutputSettings {
+    // the order next_output_mode goes through the modes in: the preferred
+    // one, then the rest, each in the given angle unit first. Fractions are
+    // then shown in radians and degrees, decimals in every unit, degrees,
+    // minutes and seconds only in degrees, and other bases in any one unit
+    // (as angles aren't whole numbers)
+    fn modes(&self, angle_unit: AngleUnit) -> Vec<(OutputMode, AngleUnit)> {
+        let units = |others: &[AngleUnit]| {
+            let mut units = vec![angle_unit];
+            units.extend(others.iter().filter(|unit| **unit != angle_unit));
+            units
+        };
+
+        let mut modes = vec![self.output_mode];
+        modes.extend(
+            [
+                OutputMode::Fraction,
+                OutputMode::MixedFraction,
+                OutputMode::Decimal,
+                OutputMode::DegreesMinutesSeconds,
+                OutputMode::Hexadecimal,
+                OutputMode::Binary,
+                OutputMode::Octal,
+            ]
+            .into_iter()
+            .filter(|mode| *mode != self.output_mode),
+        );
+
+        modes
+            .into_iter()
+            .flat_map(|mode| {
+                let units = match mode {
+                    OutputMode::Fraction | OutputMode::MixedFraction => {
+                        units(&[AngleUnit::Radians, AngleUnit::Degrees])
+                    }
+                    OutputMode::Decimal => units(&[
+                        AngleUnit::Radians,
+                        AngleUnit::Degrees,
+                        AngleUnit::Gradians,
+                        AngleUnit::Turns,
+                    ]),
+                    OutputMode::DegreesMinutesSeconds => vec![AngleUnit::Degrees],
+                    OutputMode::Hexadecimal | OutputMode::Binary | OutputMode::Octal => {
+                        vec![angle_unit]
+                    }
+                };
+
+                units.into_iter().map(move |unit| (mode, unit))
+            })
+            .collect()
+    }
+}
+
+// the angle unit converted into, if that's all there is to it (eg: -> deg)
+fn angle_unit_of(units: &Expression) -> Option<AngleUnit> {
+    let [Term { fragments }] = units.as_slice() else {
+        return None;
+    };
+
+    match fragments.as_slice() {
+        [TermFragment {
+            sign: Sign::Positive,
+            fragment_magnitude: TermFragmentMagnitude::Unit(unit),
+            ..
+        }] => unit.angle_unit(),
+        _ => None,
+    }
 }
 
 #[derive(Debug)]
 pub struct Calculator {
-    expression: Expression,
+    statement: Statement,
+    // a snapshot of the session at the time the calculator was built
+    environment: Environment,
+    output_settings: OutputSettings,
     inexact_output_modes: InexactOutputModeIter,
     exact_output_modes: ExactOutputModeIter,
+    // how many modes next_output_mode has gone through, and what it's shown
+    // since it last went through all of them
+    output_modes_shown: usize,
+    shown_this_round: Vec<String>,
 }
 
 impl Debug for InexactOutputModeIter {
@@ -145,45 +449,442 @@ impl Calculator {
             self.inexact_output_modes.next().unwrap()
         });
 
-        let inexact = expression_to_inexact(&self.expression)?;
+        let Some(expression) = self.expression() else {
+            return Err(CalculationError::new(
+                "a function definition has no value of its own".to_string(),
+            ));
+        };
+
+        let inexact = expression_to_inexact(expression, &self.environment)?;
 
         match next_mode {
             InexactOutputMode::InexactDegrees => Ok(inexact.into_degrees()),
             InexactOutputMode::InexactRadians => Ok(inexact.into_radians()),
+            InexactOutputMode::InexactGradians => Ok(inexact.into_unit(AngleUnit::Gradians)),
+            InexactOutputMode::InexactTurns => Ok(inexact.into_unit(AngleUnit::Turns)),
+            InexactOutputMode::InexactDegreesMinutesSeconds => {
+                Ok(inexact.into_degrees_minutes_seconds())
+            }
+        }
+    }
+
+    /// Evaluates the expression exactly (eg: `sin(45deg)` gives `√2/2`) and
+    /// shows it in the next exact output mode.
+    ///
+    /// Returns None if the result has no known exact form, in which case
+    /// `next_inexact_output_mode` should be used instead.
+    pub fn next_exact_output_mode(&mut self) -> Option<Exact> {
+        let next_mode = self.exact_output_modes.next().unwrap_or_else(|| {
+            self.exact_output_modes = ExactOutputMode::iter();
+            self.exact_output_modes.next().unwrap()
+        });
+
+        let exact = expression_to_exact(self.expression()?, &self.environment)?;
+
+        match next_mode {
+            ExactOutputMode::ImproperFractionRadians => {
+                Some(exact.into_radians()?.into_improper_fraction())
+            }
+            ExactOutputMode::ImproperFractionDegrees => {
+                Some(exact.into_degrees()?.into_improper_fraction())
+            }
+            ExactOutputMode::MixedFractionRadians => {
+                Some(exact.into_radians()?.into_mixed_fraction())
+            }
+            ExactOutputMode::MixedFractionDegrees => {
+                Some(exact.into_degrees()?.into_mixed_fraction())
+            }
+        }
+    }
+
+    /// Shows the result in the next output mode, going through the exact ones
+    /// (eg: 7/2, then 3 1/2) and then the inexact ones (eg: 3.5), and around
+    /// again, starting with the ones chosen in the output settings. Modes
+    /// that look the same as one already shown are skipped.
+    pub fn next_output_mode(&mut self) -> Result<String, CalculationError> {
+        let modes = self.modes();
+
+        loop {
+            let index = self.output_modes_shown % modes.len();
+            self.output_modes_shown += 1;
+
+            if index == 0 {
+                self.shown_this_round.clear();
+            }
+
+            let (mode, unit) = modes[index];
+
+            let output = match mode {
+                OutputMode::Decimal | OutputMode::DegreesMinutesSeconds => {
+                    self.inexact_output(mode, unit)?
+                }
+                OutputMode::Hexadecimal | OutputMode::Binary | OutputMode::Octal => {
+                    match self.integer_output(mode) {
+                        Ok(output) => output,
+                        // only whole numbers are shown in other bases, which is
+                        // only worth saying if it's the preferred mode
+                        Err(e) if index == 0 => return Err(e),
+                        Err(_) => continue,
+                    }
+                }
+                mode => match self.exact_output(mode, unit) {
+                    Some(exact) => exact.to_string(),
+                    // no exact form, so there's only the decimal modes
+                    None => continue,
+                },
+            };
+
+            if !self.shown_this_round.contains(&output) {
+                self.shown_this_round.push(output.clone());
+                return Ok(output);
+            }
+        }
+    }
+
+    // the modes next_output_mode goes through. A conversion into an angle unit
+    // (eg: 30deg -> rad) is only shown in that unit, and any other conversion
+    // only as a decimal
+    fn modes(&self) -> Vec<(OutputMode, AngleUnit)> {
+        let session_unit = self.environment.angle_unit();
+
+        let Statement::Conversion { units, .. } = &self.statement else {
+            return self.output_settings.modes(session_unit);
+        };
+
+        match angle_unit_of(units) {
+            Some(angle_unit) => self
+                .output_settings
+                .modes(angle_unit)
+                .into_iter()
+                .filter(|(mode, unit)| {
+                    *unit == angle_unit && *mode != OutputMode::DegreesMinutesSeconds
+                })
+                .collect(),
+            None => vec![(OutputMode::Decimal, session_unit)],
+        }
+    }
+
+    fn exact_output(&self, mode: OutputMode, unit: AngleUnit) -> Option<Exact> {
+        let mut exact = expression_to_exact(self.expression()?, &self.environment)?;
+
+        if let Statement::Conversion { .. } = self.statement {
+            exact = exact.into_angle(self.environment.angle_unit());
+        }
+
+        let exact = exact.into_unit(unit)?;
+
+        match mode {
+            OutputMode::MixedFraction => Some(exact.into_mixed_fraction()),
+            _ => Some(exact.into_improper_fraction()),
+        }
+    }
+
+    // a whole number in another base, eg: 0x1F. Anything else is an error
+    // rather than being rounded
+    fn integer_output(&self, mode: OutputMode) -> Result<String, CalculationError> {
+        // as a decimal first, so any error working it out comes up
+        let decimal = self.inexact_output(OutputMode::Decimal, self.environment.angle_unit())?;
+        let expression = self.expression().expect("it has a value, as it was shown");
+
+        let integer = expression_to_exact(expression, &self.environment)
+            .and_then(|exact| exact.as_integer())
+            .or_else(|| {
+                expression_to_inexact(expression, &self.environment)
+                    .ok()?
+                    .as_integer()
+            });
+
+        let Some(integer) = integer else {
+            let base = match mode {
+                OutputMode::Hexadecimal => "hexadecimal",
+                OutputMode::Binary => "binary",
+                _ => "octal",
+            };
+
+            return Err(CalculationError::new(format!(
+                "only whole numbers can be shown in {base}, not {decimal}"
+            )));
+        };
+
+        // in programmer mode, negative numbers are shown as they're stored,
+        // eg: -1 as an i8 is 0xFF
+        let (sign, magnitude) = match self.environment.word_size() {
+            Some(word_size) => ("", word_size.bits_of(integer).unsigned_abs()),
+            None if integer < 0 => ("-", integer.unsigned_abs()),
+            None => ("", integer.unsigned_abs()),
+        };
+
+        Ok(match mode {
+            OutputMode::Hexadecimal => format!("{sign}0x{magnitude:X}"),
+            OutputMode::Binary => format!("{sign}0b{magnitude:b}"),
+            _ => format!("{sign}0o{magnitude:o}"),
+        })
+    }
+
+    fn inexact_output(
+        &self,
+        mode: OutputMode,
+        unit: AngleUnit,
+    ) -> Result<String, CalculationError> {
+        let Some(expression) = self.expression() else {
+            return Err(CalculationError::new(
+                "a function definition has no value of its own".to_string(),
+            ));
+        };
+
+        // whole numbers are written out as they are, rather than rounded
+        // through a decimal, eg: 18446744073709551615 as a u64
+        if self.environment.word_size().is_some() && mode == OutputMode::Decimal {
+            return Ok(expression_to_integer(expression, &self.environment)?.to_string());
+        }
+
+        let inexact = expression_to_inexact(expression, &self.environment)?;
+
+        if inexact.is_nan() {
+            return Err(CalculationError::new("math error".to_string()));
+        }
+
+        if let Statement::Conversion {
+            units, units_name, ..
+        } = &self.statement
+        {
+            let value = inexact.in_units_of(units, &self.environment)?;
+
+            let value = self.output_settings.number_format.format(value);
+
+            return Ok(format!("{value} {units_name}"));
+        }
+
+        let inexact = match mode {
+            OutputMode::DegreesMinutesSeconds => inexact.into_degrees_minutes_seconds(),
+            _ => inexact.into_unit(unit),
+        };
+
+        Ok(inexact.format(&self.output_settings.number_format))
+    }
+
+    /// Changes how results are shown, eg: to the user's preferences.
+    pub fn with_output_settings(mut self, output_settings: OutputSettings) -> Self {
+        self.output_settings = output_settings;
+        self
+    }
+
+    /// Evaluates the statement and records its effect on the session, eg:
+    /// `x = 2` binds `x` so that later calculations can use it.
+    ///
+    /// Every successful result is also added to the history, so later
+    /// calculations can refer back to it with `ans`.
+    pub fn store_result(&self, environment: &mut Environment) -> Result<(), CalculationError> {
+        let expression = match &self.statement {
+            Statement::FunctionDefinition { name, function } => {
+                environment.define(*name, Rc::clone(function));
+                return Ok(());
+            }
+            Statement::Expression(expression) => expression,
+            Statement::Assignment { expression, .. } => expression,
+            Statement::Conversion { expression, .. } => expression,
+        };
+
+        let value = Value::evaluate(expression, &self.environment)?;
+
+        if value.inexact.is_nan() {
+            return Err(CalculationError::new("math error".to_string()));
+        }
+
+        match &self.statement {
+            Statement::Assignment { variable, .. } => environment.assign(*variable, value.clone()),
+            // the quantity itself is kept, so it's only checked it can be converted
+            Statement::Conversion { units, .. } => {
+                value
+                    .inexact
+                    .clone()
+                    .in_units_of(units, &self.environment)?;
+            }
+            _ => {}
+        }
+
+        environment.push_to_history(value);
+
+        Ok(())
+    }
+
+    /// Whether the input defines a function (eg: `f(x) = x^2 + 1`), which
+    /// has nothing to show until `store_result` adds it to the session.
+    pub fn is_function_definition(&self) -> bool {
+        matches!(self.statement, Statement::FunctionDefinition { .. })
+    }
+
+    fn expression(&self) -> Option<&Expression> {
+        match &self.statement {
+            Statement::Expression(expression) => Some(expression),
+            Statement::Assignment { expression, .. } => Some(expression),
+            Statement::Conversion { expression, .. } => Some(expression),
+            Statement::FunctionDefinition { .. } => None,
         }
     }
 
     pub fn build(from: &ErasableCluster) -> Result<Self, ParsingError> {
+        Self::build_with_environment(from, &Environment::new())
+    }
+
+    /// Like `build`, but variables are looked up in the given session.
+    pub fn build_with_environment(
+        from: &ErasableCluster,
+        environment: &Environment,
+    ) -> Result<Self, ParsingError> {
         let iterator = from.iter();
 
         Ok(Calculator {
-            expression: parse_into_expression(iterator)?,
+            statement: parse_into_statement(iterator)?,
+            environment: environment.clone(),
+            output_settings: OutputSettings::default(),
             inexact_output_modes: InexactOutputMode::iter(),
             exact_output_modes: ExactOutputMode::iter(),
+            output_modes_shown: 0,
+            shown_this_round: Vec::new(),
         })
     }
 }
 
-fn simplify_term_fragment(fragment: &TermFragment) -> TermFragment {
-    TermFragment {
-        fragment_magnitude: match &fragment.fragment_magnitude {
-            TermFragmentMagnitude::Bracket(expression) => {
-                TermFragmentMagnitude::Bracket(simplify_expression(expression))
-            }
-            // TermFragmentMagnitude::Function(function) => {}
-            _ => todo!(),
-        },
-        ..(*fragment)
+#[cfg(test)]
+mod tests {
+    use crate::calculation::formatting::{Notation, Precision};
+
+    use super::*;
+
+    fn outputs(input: &str, count: usize) -> Vec<String> {
+        let cluster = ErasableCluster::build(input).unwrap();
+        let mut calc = Calculator::build(&cluster).unwrap();
+
+        (0..count)
+            .map(|_| calc.next_output_mode().unwrap())
+            .collect()
     }
-}
 
-fn simplify_term(term: &Term) -> Term {
-    let simplified_fragments: Vec<TermFragment> =
-        term.fragments.iter().map(simplify_term_fragment).collect();
+    #[test]
+    fn output_modes_are_cycled_through() {
+        assert_eq!(outputs("7/2", 3), ["7/2", "3 1/2", "3.5"]);
+        assert_eq!(outputs("7/2", 4)[3], "7/2");
+        assert_eq!(
+            outputs("S(1/2)", 4),
+            [
+                "π/6 rad",
+                "30 deg",
+                "0.5235987755982989 rad",
+                "30.000000000000004 deg"
+            ]
+        );
+        // the fraction, mixed fraction and decimal modes look the same
+        assert_eq!(outputs("2", 5), ["2", "0x2", "0b10", "0o2", "2"]);
+    }
 
-    todo!()
-}
+    #[test]
+    fn output_settings_choose_the_first_mode() {
+        let settings = OutputSettings {
+            output_mode: OutputMode::Decimal,
+            number_format: NumberFormat {
+                precision: Precision::DecimalPlaces(3),
+                ..NumberFormat::default()
+            },
+        };
+        let mut environment = Environment::new();
+        environment.set_angle_unit(AngleUnit::Degrees);
+
+        let cluster = ErasableCluster::build("S(1/2)").unwrap();
+        let mut calc = Calculator::build_with_environment(&cluster, &environment)
+            .unwrap()
+            .with_output_settings(settings);
+
+        let outputs: Vec<String> = (0..8).map(|_| calc.next_output_mode().unwrap()).collect();
+        assert_eq!(
+            outputs,
+            [
+                "30.000 deg",
+                "0.524 rad",
+                "33.333 grad",
+                "0.083 turn",
+                "30 deg",
+                "π/6 rad",
+                "30°0'0.000\"",
+                "30.000 deg"
+            ]
+        );
+    }
+
+    #[test]
+    fn quantities_are_converted() {
+        assert_eq!(outputs("5 km/h -> m/s", 1), ["1.3888888888888888 m/s"]);
+        assert_eq!(outputs("2 kg m/s^2 -> N", 1), ["2 N"]);
+        assert_eq!(
+            outputs("30d -> rad", 2),
+            ["π/6 rad", "0.5235987755982988 rad"]
+        );
+
+        assert_eq!(outputs("1 mi in km", 1), ["1.609344 km"]);
+        assert_eq!(outputs("10 °C/s -> °F/s", 1), ["18 °F/s"]);
+        assert_eq!(outputs("0 °C", 1), ["273.15 K"]);
+        assert_eq!(outputs("3 A * 2 s -> C", 1), ["6 C"]);
+        assert_eq!(outputs("2 sin(30d)", 1), ["1"]);
+        assert_eq!(outputs("(2 m)^(2) m", 1), ["4 m^3"]);
+        assert_eq!(outputs("2A * 3 s", 1), ["6 s A"]);
 
-fn simplify_expression(expression: &Expression) -> Expression {
-    todo!()
+        let cluster = ErasableCluster::build("5 m -> s").unwrap();
+        let mut calc = Calculator::build(&cluster).unwrap();
+        assert_eq!(
+            calc.next_output_mode().unwrap_err().to_string(),
+            "error: can't convert m into s"
+        );
+    }
+
+    #[test]
+    fn decimals_are_written_in_the_number_format() {
+        let output = |input: &str, number_format| {
+            let cluster = ErasableCluster::build(input).unwrap();
+            let mut calc =
+                Calculator::build(&cluster)
+                    .unwrap()
+                    .with_output_settings(OutputSettings {
+                        output_mode: OutputMode::Decimal,
+                        number_format,
+                    });
+
+            calc.next_output_mode().unwrap()
+        };
+
+        let engineering = NumberFormat {
+            notation: Notation::Engineering,
+            precision: Precision::SignificantFigures(3),
+            ..NumberFormat::default()
+        };
+        let grouped = NumberFormat {
+            digit_grouping: Some(','),
+            ..NumberFormat::default()
+        };
+
+        assert_eq!(output("12345.6 km", engineering), "12.3E6 m");
+        assert_eq!(output("2 km -> mi", engineering), "1.24E0 mi");
+        assert_eq!(output("1234567.5", grouped), "1,234,567.5");
+    }
+
+    #[test]
+    fn whole_numbers_are_shown_in_other_bases() {
+        assert_eq!(outputs("0x1F", 4), ["31", "0x1F", "0b11111", "0o37"]);
+        assert_eq!(outputs("-0b1011 + 0o1", 2), ["-10", "-0xA"]);
+        // fractions aren't rounded to be shown in them
+        assert_eq!(outputs("0x1F/2", 4)[3], "31/2");
+
+        let cluster = ErasableCluster::build("7/2").unwrap();
+        let mut calc = Calculator::build(&cluster)
+            .unwrap()
+            .with_output_settings(OutputSettings {
+                output_mode: OutputMode::Hexadecimal,
+                number_format: NumberFormat::default(),
+            });
+
+        assert_eq!(
+            calc.next_output_mode().unwrap_err().to_string(),
+            "error: only whole numbers can be shown in hexadecimal, not 3.5"
+        );
+        assert_eq!(calc.next_output_mode().unwrap(), "7/2");
+    }
 }
@@ -0,0 +1,379 @@
+use std::{collections::HashMap, rc::Rc};
+
+use num_traits::ToPrimitive;
+
+use crate::{
+    input_parsing::erasable::{Erasable, ErasableType},
+    shared::errors::{CalculationError, ParsingError},
+};
+
+use super::{
+    calculator::{AngleUnit, Expression, WordSize},
+    exact::{expression_to_exact, Exact},
+    inexact::{expression_to_inexact, Inexact},
+};
+
+/// A single-letter variable, eg: `x`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Variable(char);
+
+impl TryFrom<&Erasable> for Variable {
+    type Error = ParsingError;
+
+    fn try_from(erasable: &Erasable) -> Result<Self, Self::Error> {
+        if ErasableType::from(erasable) != ErasableType::Variable {
+            return Err(ParsingError::ExpectedButFound {
+                expected: "variable".to_string(),
+                found: erasable.to_string(),
+            });
+        }
+
+        match <Erasable as ToPrimitive>::to_u8(erasable) {
+            Some(code) => Ok(Variable(code as char)),
+            None => Err(ParsingError::NoSuchCharacterCode),
+        }
+    }
+}
+
+impl std::fmt::Display for Variable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// An evaluated result, kept both exactly (if it has an exact form) and as a
+/// decimal approximation.
+#[derive(Clone, Debug)]
+pub(crate) struct Value {
+    pub(crate) exact: Option<Exact>,
+    pub(crate) inexact: Inexact,
+}
+
+impl Value {
+    pub(crate) fn evaluate(
+        expression: &Expression,
+        environment: &Environment,
+    ) -> Result<Self, CalculationError> {
+        Ok(Value {
+            exact: expression_to_exact(expression, environment),
+            inexact: expression_to_inexact(expression, environment)?,
+        })
+    }
+}
+
+// with its decimal approximation
+impl From<Exact> for Value {
+    fn from(exact: Exact) -> Self {
+        Value {
+            inexact: Inexact::from(&exact),
+            exact: Some(exact),
+        }
+    }
+}
+
+/// A function defined during the session, eg: `f(x) = x^2 + 1`.
+#[derive(Debug)]
+pub(crate) struct UserFunction {
+    pub(crate) parameters: Vec<Variable>,
+    pub(crate) body: Expression,
+}
+
+/// The state of a calculator session, which calculations are evaluated against.
+#[derive(Clone, Debug, Default)]
+pub struct Environment {
+    // shared with the environments of function calls, which only add their
+    // parameters on top
+    variables: Rc<HashMap<Variable, Value>>,
+    functions: Rc<HashMap<Variable, Rc<UserFunction>>>,
+    // every result so far, oldest first
+    history: Rc<Vec<Value>>,
+    // the arguments of the user function being evaluated, which hide
+    // variables and functions of the same name
+    parameters: HashMap<Variable, Value>,
+    // the user functions currently being evaluated, outermost first, to catch
+    // recursion
+    calls: Vec<Variable>,
+    angle_unit: AngleUnit,
+    // set in programmer mode
+    word_size: Option<WordSize>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The unit numbers are taken to be in where an angle is needed, and that
+    /// angles are shown in first.
+    pub fn angle_unit(&self) -> AngleUnit {
+        self.angle_unit
+    }
+
+    pub fn set_angle_unit(&mut self, angle_unit: AngleUnit) {
+        self.angle_unit = angle_unit;
+    }
+
+    /// The size whole numbers wrap around to fit in programmer mode, where
+    /// everything is worked out as a whole number, or None outside it.
+    pub fn word_size(&self) -> Option<WordSize> {
+        self.word_size
+    }
+
+    pub fn set_word_size(&mut self, word_size: Option<WordSize>) {
+        self.word_size = word_size;
+    }
+
+    pub(crate) fn assign(&mut self, variable: Variable, value: Value) {
+        Rc::make_mut(&mut self.functions).remove(&variable);
+        Rc::make_mut(&mut self.variables).insert(variable, value);
+    }
+
+    pub(crate) fn get(&self, variable: &Variable) -> Result<&Value, CalculationError> {
+        if let Some(argument) = self.parameters.get(variable) {
+            return Ok(argument);
+        }
+
+        if self.is_function(variable) {
+            return Err(CalculationError::new(format!(
+                "{variable} is a function, so it needs arguments, eg: {variable}(2)"
+            )));
+        }
+
+        self.variables
+            .get(variable)
+            .ok_or_else(|| CalculationError::new(format!("unknown variable {variable}")))
+    }
+
+    pub(crate) fn define(&mut self, name: Variable, function: Rc<UserFunction>) {
+        Rc::make_mut(&mut self.variables).remove(&name);
+        Rc::make_mut(&mut self.functions).insert(name, function);
+    }
+
+    pub(crate) fn is_function(&self, name: &Variable) -> bool {
+        !self.parameters.contains_key(name) && self.functions.contains_key(name)
+    }
+
+    /// Binds the arguments to the parameters of the function `name`, giving
+    /// the environment its body should be evaluated in: the session's, with
+    /// just the parameters on top (and not those of any function calling it).
+    pub(crate) fn call(
+        &self,
+        name: &Variable,
+        arguments: Vec<Value>,
+    ) -> Result<(Rc<UserFunction>, Environment), CalculationError> {
+        let function = self
+            .functions
+            .get(name)
+            .ok_or_else(|| CalculationError::new(format!("unknown function {name}")))?;
+
+        // eg: g -> m -> g, when g calls m, which calls g
+        if let Some(start) = self.calls.iter().position(|call| call == name) {
+            let cycle: Vec<String> = self.calls[start..]
+                .iter()
+                .chain([name])
+                .map(|call| call.to_string())
+                .collect();
+
+            return Err(CalculationError::new(format!(
+                "functions can't call themselves: {}",
+                cycle.join(" -> ")
+            )));
+        }
+
+        if function.parameters.len() != arguments.len() {
+            return Err(CalculationError::new(format!(
+                "{name} takes {} argument(s) but was given {}",
+                function.parameters.len(),
+                arguments.len()
+            )));
+        }
+
+        let mut calls = self.calls.clone();
+        calls.push(*name);
+
+        let environment = Environment {
+            variables: Rc::clone(&self.variables),
+            functions: Rc::clone(&self.functions),
+            history: Rc::clone(&self.history),
+            parameters: function.parameters.iter().copied().zip(arguments).collect(),
+            calls,
+            ..*self
+        };
+
+        Ok((Rc::clone(function), environment))
+    }
+
+    pub(crate) fn push_to_history(&mut self, value: Value) {
+        Rc::make_mut(&mut self.history).push(value);
+    }
+
+    /// The latest result if `index` is None, otherwise the `index`th result
+    /// of the session (starting from 1).
+    pub(crate) fn answer(&self, index: Option<usize>) -> Result<&Value, CalculationError> {
+        let answer = match index {
+            None => self.history.last(),
+            Some(index) => index.checked_sub(1).and_then(|i| self.history.get(i)),
+        };
+
+        answer.ok_or_else(|| match index {
+            None => CalculationError::new("there is no previous result yet".to_string()),
+            Some(index) => CalculationError::new(format!("there is no result ans{index} yet")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        calculation::calculator::Calculator, input_parsing::erasable_cluster::ErasableCluster,
+    };
+
+    use super::*;
+
+    fn run(input: &str, environment: &mut Environment) -> Calculator {
+        let cluster = ErasableCluster::build(input).unwrap();
+        let calc = Calculator::build_with_environment(&cluster, environment).unwrap();
+        calc.store_result(environment).unwrap();
+        calc
+    }
+
+    #[test]
+    fn assigned_variables_can_be_used_later() {
+        let mut environment = Environment::new();
+
+        run("x = 3", &mut environment);
+        run("y = 2x + 1", &mut environment);
+
+        let mut calc = run("xy/2", &mut environment);
+        assert_eq!(calc.next_exact_output_mode().unwrap().to_string(), "21/2");
+
+        // reassigning replaces the old value
+        run("x = s(30d)", &mut environment);
+        let mut calc = run("x", &mut environment);
+        assert_eq!(calc.next_exact_output_mode().unwrap().to_string(), "1/2");
+    }
+
+    #[test]
+    fn previous_results_can_be_referred_to() {
+        let mut environment = Environment::new();
+
+        run("R(2, 8)", &mut environment);
+        run("1 + 2", &mut environment);
+
+        let mut calc = run("2 ans", &mut environment);
+        assert_eq!(calc.next_exact_output_mode().unwrap().to_string(), "6");
+
+        let mut calc = run("A1 * A2 + A", &mut environment);
+        assert_eq!(
+            calc.next_exact_output_mode().unwrap().to_string(),
+            "6√2 + 6"
+        );
+
+        // history keeps the decimal approximation when there is no exact form
+        run("e", &mut environment);
+        let mut calc = run("A", &mut environment);
+        assert!(calc.next_exact_output_mode().is_none());
+        assert_eq!(
+            calc.next_inexact_output_mode().unwrap().to_string(),
+            std::f64::consts::E.to_string()
+        );
+    }
+
+    #[test]
+    fn missing_results_are_an_error() {
+        let environment = Environment::new();
+
+        for input in ["A", "A1", "A0"] {
+            let cluster = ErasableCluster::build(input).unwrap();
+            let mut calc = Calculator::build_with_environment(&cluster, &environment).unwrap();
+            assert!(calc.next_inexact_output_mode().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn user_defined_functions_can_be_called() {
+        let mut environment = Environment::new();
+
+        run("f(x) = x^2 + 1", &mut environment);
+        run("g(x, y) = f(x) - y", &mut environment);
+
+        let mut calc = run("f(3) + g(R(2, 2), 1/2)", &mut environment);
+        assert_eq!(calc.next_exact_output_mode().unwrap().to_string(), "25/2");
+
+        // parameters don't leak out, and shadow variables of the same name
+        run("x = 10", &mut environment);
+        let mut calc = run("f(2) + x", &mut environment);
+        assert_eq!(calc.next_exact_output_mode().unwrap().to_string(), "15");
+
+        // a variable followed by brackets is still multiplied
+        let mut calc = run("x(2)", &mut environment);
+        assert_eq!(calc.next_exact_output_mode().unwrap().to_string(), "20");
+    }
+
+    #[test]
+    fn calls_share_the_session_rather_than_copying_it() {
+        let mut environment = Environment::new();
+
+        run("y = 5", &mut environment);
+        run("f(x) = x + y", &mut environment);
+        run("g(y) = f(1)", &mut environment);
+
+        let argument = Value::from(Exact::from_integer(2).unwrap());
+        let (_, called) = environment.call(&Variable('f'), vec![argument]).unwrap();
+        assert!(Rc::ptr_eq(&called.history, &environment.history));
+        assert!(Rc::ptr_eq(&called.variables, &environment.variables));
+
+        // a function only sees its own parameters, not its caller's
+        let mut calc = run("g(100)", &mut environment);
+        assert_eq!(calc.next_exact_output_mode().unwrap().to_string(), "6");
+
+        let nested = format!("{}1{}", "f(".repeat(30), ")".repeat(30));
+        let mut calc = run(&nested, &mut environment);
+        assert_eq!(calc.next_exact_output_mode().unwrap().to_string(), "151");
+    }
+
+    #[test]
+    fn invalid_calls_are_an_error() {
+        let mut environment = Environment::new();
+
+        run("f(x) = 2x", &mut environment);
+        run("g(x) = f(x) + m(x)", &mut environment);
+        run("m(x) = g(x)", &mut environment);
+
+        for (input, error) in [
+            ("f(1, 2)", "error: f takes 1 argument(s) but was given 2"),
+            (
+                "2f",
+                "error: f is a function, so it needs arguments, eg: f(2)",
+            ),
+            ("k(1, 2)", "error: unknown function k"),
+            (
+                "g(1)",
+                "error: functions can't call themselves: g -> m -> g",
+            ),
+        ] {
+            let cluster = ErasableCluster::build(input).unwrap();
+            let mut calc = Calculator::build_with_environment(&cluster, &environment).unwrap();
+
+            assert!(calc.next_exact_output_mode().is_none(), "{input}");
+            assert_eq!(
+                calc.next_inexact_output_mode().unwrap_err().to_string(),
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_variables_are_an_error() {
+        let mut environment = Environment::new();
+        let cluster = ErasableCluster::build("2z").unwrap();
+        let mut calc = Calculator::build_with_environment(&cluster, &environment).unwrap();
+
+        assert!(calc.next_exact_output_mode().is_none());
+        assert_eq!(
+            calc.next_inexact_output_mode().unwrap_err().to_string(),
+            "error: unknown variable z"
+        );
+        assert!(calc.store_result(&mut environment).is_err());
+    }
+}
@@ -0,0 +1,1015 @@
+// contains the exact evaluation of expressions and the Exact definition
+//
+// Evaluation returns None whenever the result has no exact form that can be
+// represented (eg: anything involving e, or the sine of an arbitrary angle), so
+// callers can fall back to Inexact.
+
+use std::{f64::consts::PI, ops::Neg};
+
+use crate::shared::sign::Sign;
+
+use super::{
+    calculation_precision::{
+        FloatingPointPrecison, SignedValuePrecision, UnsignedValuePrecision, WideValuePrecision,
+    },
+    calculator::{
+        AngleUnit, Expression, Function, MultipliedOrDivided, NamedConstant, Term, TermFragment,
+        TermFragmentMagnitude, UnnamedConstant,
+    },
+    environment::{Environment, Value},
+    integer::{bitwise_to_integer, expression_to_integer},
+    rational_number::RationalNumber,
+    surd::Surd,
+};
+
+// coefficient * surd * π^pi_exponent
+#[derive(Clone, Debug, PartialEq)]
+struct ExactTerm {
+    coefficient: RationalNumber,
+    surd: Surd,
+    pi_exponent: i32,
+}
+
+impl ExactTerm {
+    fn checked_mul(&self, rhs: &ExactTerm) -> Option<ExactTerm> {
+        let (outside_the_root, surd) = self.surd.checked_mul(rhs.surd)?;
+
+        Some(ExactTerm {
+            coefficient: self
+                .coefficient
+                .checked_mul(rhs.coefficient)?
+                .checked_mul(RationalNumber::from_integer(outside_the_root))?,
+            surd,
+            pi_exponent: self.pi_exponent.checked_add(rhs.pi_exponent)?,
+        })
+    }
+
+    // the root is moved to the numerator, eg: 1/(2√3) = √3/6
+    fn reciprocal(&self) -> Option<ExactTerm> {
+        let (surd, divisor) = self.surd.reciprocal()?;
+
+        Some(ExactTerm {
+            coefficient: self
+                .coefficient
+                .checked_mul(RationalNumber::from_integer(divisor))?
+                .reciprocal()?,
+            surd,
+            pi_exponent: self.pi_exponent.checked_neg()?,
+        })
+    }
+
+    // ⁿ√(p/q) = ⁿ√(p * qⁿ⁻¹)/q, so the result has no roots in its denominator
+    fn checked_root(&self, degree: u32) -> Option<ExactTerm> {
+        if self.pi_exponent != 0 {
+            return None;
+        }
+
+        let is_negative = self.coefficient.sign() == Sign::Negative;
+
+        // even roots of negative numbers aren't real
+        if is_negative && degree.is_multiple_of(2) {
+            return None;
+        }
+
+        let numerator = self.coefficient.numerator();
+        let denominator = self.coefficient.denominator();
+
+        let (outside_the_root, root_of_coefficient) = Surd::new(
+            degree,
+            numerator.checked_mul(denominator.checked_pow(degree.checked_sub(1)?)?)?,
+        )?;
+        let coefficient = RationalNumber::from_integer(outside_the_root)
+            .checked_div(RationalNumber::from_integer(denominator))?;
+
+        let (outside_the_root, root_of_surd) = self.surd.checked_root(degree)?;
+
+        let root_of_coefficient = ExactTerm {
+            coefficient: if is_negative {
+                -coefficient
+            } else {
+                coefficient
+            },
+            surd: root_of_coefficient,
+            pi_exponent: 0,
+        };
+        let root_of_surd = ExactTerm {
+            coefficient: RationalNumber::from_integer(outside_the_root),
+            surd: root_of_surd,
+            pi_exponent: 0,
+        };
+
+        root_of_coefficient.checked_mul(&root_of_surd)
+    }
+
+    fn to_float(&self) -> FloatingPointPrecison {
+        self.coefficient.to_float() * self.surd.to_float() * PI.powi(self.pi_exponent)
+    }
+
+    // the term without its sign
+    fn magnitude_to_string(&self, fraction_style: FractionStyle) -> String {
+        let numerator = self.coefficient.numerator();
+        let denominator = self.coefficient.denominator();
+
+        let root = self.surd.to_string();
+        let pi = match self.pi_exponent.abs() {
+            0 => String::new(),
+            1 => "π".to_string(),
+            exponent => format!("π^{exponent}"),
+        };
+        let (pi_above, pi_below) = if self.pi_exponent > 0 {
+            (pi, String::new())
+        } else {
+            (String::new(), pi)
+        };
+
+        let is_plain_rational = root.is_empty() && pi_above.is_empty() && pi_below.is_empty();
+
+        if is_plain_rational
+            && fraction_style == FractionStyle::Mixed
+            && denominator != 1
+            && numerator > denominator
+        {
+            return format!(
+                "{} {}/{}",
+                numerator / denominator,
+                numerator % denominator,
+                denominator
+            );
+        }
+
+        // no need for a leading 1 in 1√2 or 1π
+        let above = if numerator == 1 && !(root.is_empty() && pi_above.is_empty()) {
+            format!("{pi_above}{root}")
+        } else {
+            format!("{numerator}{pi_above}{root}")
+        };
+
+        let below = match (denominator, pi_below.is_empty()) {
+            (1, true) => return above,
+            (1, false) => pi_below,
+            (_, true) => denominator.to_string(),
+            (_, false) => format!("({denominator}{pi_below})"),
+        };
+
+        format!("{above}/{below}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum FractionStyle {
+    #[default]
+    Improper,
+    Mixed,
+}
+
+#[derive(Clone, Debug)]
+pub struct Exact {
+    // always simplified: like terms are combined, zero terms removed and the
+    // rest sorted, so two equal values have equal terms
+    terms: Vec<ExactTerm>,
+    unit: Option<AngleUnit>,
+    fraction_style: FractionStyle,
+}
+
+impl std::fmt::Display for Exact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut result = String::new();
+
+        if self.terms.is_empty() {
+            result.push('0');
+        }
+
+        for (index, term) in self.terms.iter().enumerate() {
+            let is_negative = term.coefficient.sign() == Sign::Negative;
+
+            match (index, is_negative) {
+                (0, true) => result.push('-'),
+                (0, false) => (),
+                (_, true) => result.push_str(" - "),
+                (_, false) => result.push_str(" + "),
+            }
+
+            result.push_str(&term.magnitude_to_string(self.fraction_style));
+        }
+
+        if let Some(unit) = self.unit {
+            result.push(' ');
+            result.push_str(unit.symbol());
+        }
+
+        result.fmt(f)
+    }
+}
+
+impl Neg for Exact {
+    type Output = Exact;
+
+    fn neg(mut self) -> Self::Output {
+        for term in self.terms.iter_mut() {
+            term.coefficient = -term.coefficient;
+        }
+
+        self
+    }
+}
+
+impl Exact {
+    fn from_terms(terms: Vec<ExactTerm>, unit: Option<AngleUnit>) -> Option<Self> {
+        let mut simplified: Vec<ExactTerm> = Vec::with_capacity(terms.len());
+
+        for term in terms {
+            let like_term = simplified
+                .iter_mut()
+                .find(|t| t.surd == term.surd && t.pi_exponent == term.pi_exponent);
+
+            match like_term {
+                Some(like_term) => {
+                    like_term.coefficient = like_term.coefficient.checked_add(term.coefficient)?
+                }
+                None => simplified.push(term),
+            }
+        }
+
+        simplified.retain(|term| !term.coefficient.is_zero());
+        // eg: π + √2 - 1
+        simplified.sort_by_key(|term| (-term.pi_exponent, term.surd.is_one(), term.surd));
+
+        Some(Exact {
+            terms: simplified,
+            unit,
+            fraction_style: FractionStyle::default(),
+        })
+    }
+
+    fn rational(value: RationalNumber) -> Self {
+        Exact {
+            terms: if value.is_zero() {
+                vec![]
+            } else {
+                vec![ExactTerm {
+                    coefficient: value,
+                    surd: Surd::one(),
+                    pi_exponent: 0,
+                }]
+            },
+            unit: None,
+            fraction_style: FractionStyle::default(),
+        }
+    }
+
+    fn pi() -> Self {
+        Exact {
+            terms: vec![ExactTerm {
+                coefficient: RationalNumber::one(),
+                surd: Surd::one(),
+                pi_exponent: 1,
+            }],
+            unit: None,
+            fraction_style: FractionStyle::default(),
+        }
+    }
+
+    // (numerator / denominator) * √radicand; used for the tables of special values
+    fn special_value(
+        numerator: SignedValuePrecision,
+        denominator: SignedValuePrecision,
+        radicand: UnsignedValuePrecision,
+    ) -> Self {
+        let (_, surd) = Surd::new(2, radicand).expect("special values have small radicands");
+
+        Exact {
+            terms: vec![ExactTerm {
+                coefficient: RationalNumber::new(numerator, denominator),
+                surd,
+                pi_exponent: 0,
+            }],
+            unit: None,
+            fraction_style: FractionStyle::default(),
+        }
+    }
+
+    fn with_unit(mut self, unit: Option<AngleUnit>) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    // None if the value isn't rational (or isn't a plain number)
+    fn as_rational(&self) -> Option<RationalNumber> {
+        match &self.terms[..] {
+            [] => Some(RationalNumber::zero()),
+            [term] if term.surd.is_one() && term.pi_exponent == 0 => Some(term.coefficient),
+            _ => None,
+        }
+    }
+
+    fn has_same_value_as(&self, other: &Exact) -> bool {
+        self.terms == other.terms
+    }
+
+    fn scale_to_unit(
+        self,
+        factor: RationalNumber,
+        pi_exponent: i32,
+        unit: AngleUnit,
+    ) -> Option<Self> {
+        let terms: Option<Vec<ExactTerm>> = self
+            .terms
+            .into_iter()
+            .map(|term| {
+                Some(ExactTerm {
+                    coefficient: term.coefficient.checked_mul(factor)?,
+                    pi_exponent: term.pi_exponent.checked_add(pi_exponent)?,
+                    ..term
+                })
+            })
+            .collect();
+
+        Some(Exact {
+            terms: terms?,
+            unit: Some(unit),
+            fraction_style: self.fraction_style,
+        })
+    }
+
+    /// The angle in the given unit; numbers without a unit are left as they
+    /// are.
+    pub fn into_unit(self, unit: AngleUnit) -> Option<Self> {
+        // how many of the unit make a half turn, for the units that aren't
+        // measured in terms of π
+        fn half_turn(unit: AngleUnit) -> Option<RationalNumber> {
+            match unit {
+                AngleUnit::Degrees => Some(RationalNumber::from_integer(180)),
+                AngleUnit::Radians => None,
+                AngleUnit::Gradians => Some(RationalNumber::from_integer(200)),
+                AngleUnit::Turns => Some(RationalNumber::new(1, 2)),
+            }
+        }
+
+        let Some(from) = self.unit else {
+            return Some(self);
+        };
+
+        if from == unit {
+            return Some(self);
+        }
+
+        // eg: multiply by π/180 from degrees, then by 200/π into gradians
+        let radians = match half_turn(from) {
+            Some(half_turn) => self.scale_to_unit(
+                RationalNumber::one().checked_div(half_turn)?,
+                1,
+                AngleUnit::Radians,
+            )?,
+            None => self,
+        };
+
+        match half_turn(unit) {
+            Some(half_turn) => radians.scale_to_unit(half_turn, -1, unit),
+            None => Some(radians.with_unit(Some(unit))),
+        }
+    }
+
+    pub fn into_radians(self) -> Option<Self> {
+        self.into_unit(AngleUnit::Radians)
+    }
+
+    pub fn into_degrees(self) -> Option<Self> {
+        self.into_unit(AngleUnit::Degrees)
+    }
+
+    pub fn into_mixed_fraction(mut self) -> Self {
+        self.fraction_style = FractionStyle::Mixed;
+        self
+    }
+
+    pub fn into_improper_fraction(mut self) -> Self {
+        self.fraction_style = FractionStyle::Improper;
+        self
+    }
+
+    /// The value if it's a whole number (without a unit) that fits.
+    pub(crate) fn as_integer(&self) -> Option<WideValuePrecision> {
+        let value = self.as_rational().filter(|value| value.is_integer())?;
+        let magnitude = WideValuePrecision::from(value.numerator());
+
+        match (self.unit, value.sign()) {
+            (Some(_), _) => None,
+            (None, Sign::Negative) => Some(-magnitude),
+            (None, Sign::Positive) => Some(magnitude),
+        }
+    }
+
+    pub(crate) fn from_integer(value: WideValuePrecision) -> Option<Self> {
+        let magnitude = UnsignedValuePrecision::try_from(value.unsigned_abs()).ok()?;
+        let magnitude = Exact::rational(RationalNumber::from_integer(magnitude));
+
+        Some(if value < 0 {
+            magnitude.neg()
+        } else {
+            magnitude
+        })
+    }
+
+    pub fn to_float(&self) -> FloatingPointPrecison {
+        self.terms.iter().map(ExactTerm::to_float).sum()
+    }
+
+    /// The unit, if it's an angle.
+    pub(crate) fn angle_unit(&self) -> Option<AngleUnit> {
+        self.unit
+    }
+
+    // a number without a unit is taken to be in the session's angle unit
+    pub(crate) fn into_angle(self, session_unit: AngleUnit) -> Self {
+        let unit = self.unit.unwrap_or(session_unit);
+        self.with_unit(Some(unit))
+    }
+
+    // angles follow the same rules as for Inexact
+    fn checked_add(self, rhs: Exact, session_unit: AngleUnit) -> Option<Exact> {
+        let (lhs, rhs) = if self.unit.is_none() && rhs.unit.is_none() {
+            (self, rhs)
+        } else {
+            // in the unit of the angle on the left
+            let lhs = self.into_angle(session_unit);
+            let rhs = rhs.into_angle(session_unit).into_unit(lhs.unit?)?;
+            (lhs, rhs)
+        };
+
+        Exact::from_terms(lhs.terms.into_iter().chain(rhs.terms).collect(), lhs.unit)
+    }
+
+    fn checked_mul(self, rhs: Exact) -> Option<Exact> {
+        if self.unit.is_some() && rhs.unit.is_some() {
+            return None;
+        }
+
+        let unit = self.unit.or(rhs.unit);
+        let mut terms = Vec::with_capacity(self.terms.len() * rhs.terms.len());
+
+        for lhs_term in &self.terms {
+            for rhs_term in &rhs.terms {
+                terms.push(lhs_term.checked_mul(rhs_term)?);
+            }
+        }
+
+        Exact::from_terms(terms, unit)
+    }
+
+    fn checked_div(self, rhs: Exact) -> Option<Exact> {
+        match (self.unit, rhs.unit) {
+            // a number
+            (Some(_), Some(_)) => self
+                .into_radians()?
+                .with_unit(None)
+                .checked_mul(rhs.into_radians()?.with_unit(None).reciprocal()?),
+            (None, Some(_)) => None,
+            (_, None) => self.checked_mul(rhs.reciprocal()?),
+        }
+    }
+
+    fn reciprocal(self) -> Option<Exact> {
+        let unit = self.unit;
+
+        match &self.terms[..] {
+            [term] => Exact::from_terms(vec![term.reciprocal()?], unit),
+            // rationalise by multiplying above and below by the conjugate:
+            // 1/(a + b) = (a - b)/(a² - b²), where a² - b² has no square roots left
+            [first, second]
+                if first.surd.degree() <= 2
+                    && second.surd.degree() <= 2
+                    && first.pi_exponent == second.pi_exponent =>
+            {
+                let conjugate = Exact::from_terms(
+                    vec![
+                        first.clone(),
+                        ExactTerm {
+                            coefficient: -second.coefficient,
+                            ..second.clone()
+                        },
+                    ],
+                    None,
+                )?;
+                let difference_of_squares = self.with_unit(None).checked_mul(conjugate.clone())?;
+
+                if difference_of_squares.terms.len() != 1 {
+                    return None;
+                }
+
+                Some(
+                    conjugate
+                        .checked_mul(difference_of_squares.reciprocal()?)?
+                        .with_unit(unit),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    fn checked_root(self, degree: Exact) -> Option<Exact> {
+        if self.unit.is_some() {
+            return None;
+        }
+
+        let degree = degree.as_rational()?;
+
+        if !degree.is_integer() || degree.sign() == Sign::Negative {
+            return None;
+        }
+
+        let degree = u32::try_from(degree.numerator()).ok()?;
+
+        match &self.terms[..] {
+            [] => Some(self),
+            [term] => Exact::from_terms(vec![term.checked_root(degree)?], self.unit),
+            _ => None,
+        }
+    }
+
+    fn checked_pow(self, exponent: Exact) -> Option<Exact> {
+        if self.unit.is_some() {
+            return None;
+        }
+
+        let exponent = exponent.as_rational()?;
+
+        // eg: 8^(2/3) = (∛8)²
+        let base = if exponent.is_integer() {
+            self
+        } else {
+            let degree = RationalNumber::from_integer(exponent.denominator());
+            self.checked_root(Exact::rational(degree))?
+        };
+
+        let exponent = SignedValuePrecision::try_from(exponent.numerator()).ok()?
+            * exponent.sign() as SignedValuePrecision;
+
+        let mut base = base;
+        let mut result = Exact::rational(RationalNumber::one());
+        let mut remaining = exponent.unsigned_abs();
+
+        while remaining > 0 {
+            if remaining % 2 == 1 {
+                result = result.checked_mul(base.clone())?;
+            }
+
+            remaining /= 2;
+
+            if remaining > 0 {
+                base = base.clone().checked_mul(base)?;
+            }
+        }
+
+        if exponent < 0 {
+            result.reciprocal()
+        } else {
+            Some(result)
+        }
+    }
+
+    fn abs(self) -> Exact {
+        if self.to_float() < 0.0 {
+            -self
+        } else {
+            self
+        }
+    }
+
+    // the angle as a whole number of degrees in [0, 360), if it is one
+    fn whole_degrees(self, session_unit: AngleUnit) -> Option<UnsignedValuePrecision> {
+        let degrees = self
+            .into_angle(session_unit)
+            .into_unit(AngleUnit::Degrees)?
+            .as_rational()?;
+
+        if !degrees.is_integer() {
+            return None;
+        }
+
+        let magnitude = degrees.numerator() % 360;
+
+        Some(match degrees.sign() {
+            Sign::Positive => magnitude,
+            Sign::Negative => (360 - magnitude) % 360,
+        })
+    }
+
+    // degrees * π/180 in radians
+    fn radians_from_degrees(degrees: SignedValuePrecision) -> Option<Exact> {
+        Exact::from_terms(
+            vec![ExactTerm {
+                coefficient: RationalNumber::new(degrees, 180),
+                surd: Surd::one(),
+                pi_exponent: 1,
+            }],
+            Some(AngleUnit::Radians),
+        )
+    }
+}
+
+// exact values at multiples of 30° and 45°; `degrees` must be in [0, 360)
+fn sine_of_whole_degrees(degrees: UnsignedValuePrecision) -> Option<Exact> {
+    let (reference_angle, sign) = match degrees {
+        0..=90 => (degrees, Sign::Positive),
+        91..=180 => (180 - degrees, Sign::Positive),
+        181..=270 => (degrees - 180, Sign::Negative),
+        _ => (360 - degrees, Sign::Negative),
+    };
+
+    let value = match reference_angle {
+        0 => Exact::rational(RationalNumber::zero()),
+        30 => Exact::special_value(1, 2, 1),
+        45 => Exact::special_value(1, 2, 2),
+        60 => Exact::special_value(1, 2, 3),
+        90 => Exact::special_value(1, 1, 1),
+        _ => return None,
+    };
+
+    Some(match sign {
+        Sign::Positive => value,
+        Sign::Negative => -value,
+    })
+}
+
+fn cosine_of_whole_degrees(degrees: UnsignedValuePrecision) -> Option<Exact> {
+    // cos(x) = sin(x + 90°)
+    sine_of_whole_degrees((degrees + 90) % 360)
+}
+
+fn tangent_of_whole_degrees(degrees: UnsignedValuePrecision) -> Option<Exact> {
+    let degrees = degrees % 180;
+
+    let (reference_angle, sign) = if degrees <= 90 {
+        (degrees, Sign::Positive)
+    } else {
+        (180 - degrees, Sign::Negative)
+    };
+
+    let value = match reference_angle {
+        0 => Exact::rational(RationalNumber::zero()),
+        30 => Exact::special_value(1, 3, 3),
+        45 => Exact::special_value(1, 1, 1),
+        60 => Exact::special_value(1, 1, 3),
+        // undefined at 90°
+        _ => return None,
+    };
+
+    Some(match sign {
+        Sign::Positive => value,
+        Sign::Negative => -value,
+    })
+}
+
+// looks `value` up in the table of `function`, trying each of the candidate
+// angles (in degrees, within the range of the inverse function)
+fn inverse_of_special_value(
+    value: &Exact,
+    function: fn(UnsignedValuePrecision) -> Option<Exact>,
+    candidates: &[SignedValuePrecision],
+) -> Option<Exact> {
+    candidates
+        .iter()
+        .find(|degrees| {
+            let degrees = degrees.rem_euclid(360) as UnsignedValuePrecision;
+
+            match function(degrees) {
+                Some(special_value) => special_value.has_same_value_as(value),
+                None => false,
+            }
+        })
+        .and_then(|degrees| Exact::radians_from_degrees(*degrees))
+}
+
+pub(crate) fn expression_to_exact(
+    expression: &Expression,
+    environment: &Environment,
+) -> Option<Exact> {
+    // everything is a whole number in programmer mode
+    if environment.word_size().is_some() {
+        return Exact::from_integer(expression_to_integer(expression, environment).ok()?);
+    }
+
+    let mut sum: Option<Exact> = None;
+
+    for term in expression {
+        let term = term_to_exact(term, environment)?;
+
+        sum = Some(match sum {
+            Some(prev) => prev.checked_add(term, environment.angle_unit())?,
+            None => term,
+        });
+    }
+
+    sum
+}
+
+// the value, if it isn't an angle
+fn expression_to_number(expression: &Expression, environment: &Environment) -> Option<Exact> {
+    expression_to_exact(expression, environment).filter(|value| value.unit.is_none())
+}
+
+fn term_to_exact(term: &Term, environment: &Environment) -> Option<Exact> {
+    let mut product: Option<Exact> = None;
+
+    for fragment in &term.fragments {
+        let value = fragment_to_exact(fragment, environment)?;
+
+        product = Some(match (product, fragment.multiplied_or_divided) {
+            (Some(prev), MultipliedOrDivided::Divided) => prev.checked_div(value)?,
+            (Some(prev), _) => prev.checked_mul(value)?,
+            (None, MultipliedOrDivided::Divided) => {
+                Exact::rational(RationalNumber::one()).checked_div(value)?
+            }
+            (None, _) => value,
+        });
+    }
+
+    product
+}
+
+fn fragment_to_exact(fragment: &TermFragment, environment: &Environment) -> Option<Exact> {
+    let mut magnitude = magnitude_to_exact(&fragment.fragment_magnitude, environment)?;
+
+    if fragment.sign == Sign::Negative {
+        magnitude = -magnitude;
+    }
+
+    // preserve unit
+    magnitude.unit = magnitude.unit.or(fragment.angle_unit);
+
+    Some(magnitude)
+}
+
+fn magnitude_to_exact(
+    magnitude: &TermFragmentMagnitude,
+    environment: &Environment,
+) -> Option<Exact> {
+    match magnitude {
+        TermFragmentMagnitude::Bracket(expression) => expression_to_exact(expression, environment),
+        TermFragmentMagnitude::Function(function) => function_to_exact(function, environment),
+        TermFragmentMagnitude::Variable(variable) => environment.get(variable).ok()?.exact.clone(),
+        TermFragmentMagnitude::Bitwise(operation) => {
+            Exact::from_integer(bitwise_to_integer(operation, environment).ok()?)
+        }
+        // quantities other than angles are only worked out as decimals
+        TermFragmentMagnitude::Unit(unit) => Some(Exact {
+            unit: Some(unit.angle_unit()?),
+            ..Exact::rational(RationalNumber::from_integer(1))
+        }),
+        TermFragmentMagnitude::NamedConstant {
+            coefficient,
+            constant,
+        } => {
+            let coefficient = expression_to_exact(coefficient, environment)?;
+
+            match constant {
+                NamedConstant::Pi => coefficient.checked_mul(Exact::pi()),
+                // e has no exact form here
+                NamedConstant::E => None,
+                NamedConstant::Answer(index) => {
+                    coefficient.checked_mul(environment.answer(*index).ok()?.exact.clone()?)
+                }
+            }
+        }
+        TermFragmentMagnitude::NonNamedConstant(constant) => match constant {
+            UnnamedConstant::Integer(value)
+            | UnnamedConstant::Hexadecimal(value)
+            | UnnamedConstant::Binary(value)
+            | UnnamedConstant::Octal(value) => {
+                Some(Exact::rational(RationalNumber::from_integer(*value)))
+            }
+            UnnamedConstant::Decimal {
+                before_decimal_point,
+                after_decimal_point,
+            } => {
+                if before_decimal_point.is_empty() && after_decimal_point.is_empty() {
+                    return None;
+                }
+
+                // eg: 4.25 = 425/100
+                let digits = format!("{before_decimal_point}{after_decimal_point}");
+                let numerator = digits.parse::<UnsignedValuePrecision>().ok()?;
+                let denominator =
+                    (10 as UnsignedValuePrecision).checked_pow(after_decimal_point.len() as u32)?;
+
+                let value = RationalNumber::from_integer(numerator)
+                    .checked_div(RationalNumber::from_integer(denominator))?;
+
+                Some(Exact::rational(value))
+            }
+            UnnamedConstant::Fraction {
+                numerator,
+                denominator,
+            } => {
+                let numerator = expression_to_exact(numerator, environment)?;
+                let denominator = expression_to_exact(denominator, environment)?;
+
+                numerator.checked_div(denominator)
+            }
+            UnnamedConstant::Power { base, exponent } => expression_to_exact(base, environment)?
+                .checked_pow(expression_to_exact(exponent, environment)?),
+        },
+    }
+}
+
+fn function_to_exact(function: &Function, environment: &Environment) -> Option<Exact> {
+    match function {
+        Function::Absolute(expression) => Some(expression_to_exact(expression, environment)?.abs()),
+        Function::NthRoot(degree, under_the_root) => {
+            expression_to_exact(under_the_root, environment)?
+                .checked_root(expression_to_exact(degree, environment)?)
+        }
+        Function::Sin(expression) => sine_of_whole_degrees(
+            expression_to_exact(expression, environment)?
+                .whole_degrees(environment.angle_unit())?,
+        ),
+        Function::Cos(expression) => cosine_of_whole_degrees(
+            expression_to_exact(expression, environment)?
+                .whole_degrees(environment.angle_unit())?,
+        ),
+        Function::Tan(expression) => tangent_of_whole_degrees(
+            expression_to_exact(expression, environment)?
+                .whole_degrees(environment.angle_unit())?,
+        ),
+        Function::Arcsin(expression) => inverse_of_special_value(
+            &expression_to_number(expression, environment)?,
+            sine_of_whole_degrees,
+            &[-90, -60, -45, -30, 0, 30, 45, 60, 90],
+        ),
+        Function::Arccos(expression) => inverse_of_special_value(
+            &expression_to_number(expression, environment)?,
+            cosine_of_whole_degrees,
+            &[0, 30, 45, 60, 90, 120, 135, 150, 180],
+        ),
+        Function::Arctan(expression) => inverse_of_special_value(
+            &expression_to_number(expression, environment)?,
+            tangent_of_whole_degrees,
+            &[-60, -45, -30, 0, 30, 45, 60],
+        ),
+        Function::User { name, arguments } => {
+            if !environment.is_function(name) {
+                // not a function, so eg: x(2) is x times 2
+                let [argument] = arguments.as_slice() else {
+                    return None;
+                };
+
+                let variable = environment.get(name).ok()?.exact.clone()?;
+                return variable.checked_mul(expression_to_exact(argument, environment)?);
+            }
+
+            // only the exact value is needed here, so the decimal one is just
+            // the approximation of it rather than worked out again
+            let arguments = arguments
+                .iter()
+                .map(|argument| expression_to_exact(argument, environment).map(Value::from))
+                .collect::<Option<Vec<_>>>()?;
+
+            let (function, environment) = environment.call(name, arguments).ok()?;
+            expression_to_exact(&function.body, &environment)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        calculation::parsers::parse_into_expression,
+        input_parsing::erasable_cluster::ErasableCluster,
+    };
+
+    fn exact(input: &str) -> Option<Exact> {
+        let cluster = ErasableCluster::build(input).unwrap();
+        let expression = parse_into_expression(cluster.iter()).unwrap();
+
+        expression_to_exact(&expression, &Environment::new())
+    }
+
+    #[test]
+    fn trigonometric_functions_of_special_angles_are_exact() {
+        assert_eq!(exact("s(30d)").unwrap().to_string(), "1/2");
+        assert_eq!(exact("c(45d)").unwrap().to_string(), "√2/2");
+        assert_eq!(exact("t(60d)").unwrap().to_string(), "√3");
+        assert_eq!(exact("s(p/6)").unwrap().to_string(), "1/2");
+        assert_eq!(exact("c(5p/6)").unwrap().to_string(), "-√3/2");
+        assert_eq!(exact("s(-90d)").unwrap().to_string(), "-1");
+        assert_eq!(exact("t(135d) + 1").unwrap().to_string(), "0");
+    }
+
+    #[test]
+    fn inverse_trigonometric_functions_of_special_values_are_exact() {
+        let angle = exact("S(1/2)").unwrap();
+        assert_eq!(angle.to_string(), "π/6 rad");
+        assert_eq!(angle.into_degrees().unwrap().to_string(), "30 deg");
+
+        assert_eq!(exact("T(-1)").unwrap().to_string(), "-π/4 rad");
+        assert_eq!(exact("C(0)").unwrap().to_string(), "π/2 rad");
+    }
+
+    #[test]
+    fn values_without_an_exact_form_fall_back() {
+        assert!(exact("s(20d)").is_none());
+        assert!(exact("t(90d)").is_none());
+        assert!(exact("S(0.3)").is_none());
+        assert!(exact("2e").is_none());
+    }
+
+    #[test]
+    fn surds_are_simplified() {
+        assert_eq!(exact("R(2, 12)").unwrap().to_string(), "2√3");
+        assert_eq!(exact("R(3, 54)").unwrap().to_string(), "3∛2");
+        assert_eq!(exact("R(3, -8)").unwrap().to_string(), "-2");
+        assert_eq!(exact("R(3, 1/4)").unwrap().to_string(), "∛2/2");
+        assert_eq!(exact("(8)^(2/3)").unwrap().to_string(), "4");
+        assert!(exact("R(2, -4)").is_none());
+    }
+
+    #[test]
+    fn arithmetic_with_surds_is_exact() {
+        assert_eq!(exact("R(2, 2)R(2, 8)").unwrap().to_string(), "4");
+        assert_eq!(exact("R(2, 2) + R(2, 8)").unwrap().to_string(), "3√2");
+        assert_eq!(exact("R(2, 2)R(3, 2)").unwrap().to_string(), "⁶√32");
+        assert_eq!(exact("S(R(2, 2)/2)").unwrap().to_string(), "π/4 rad");
+    }
+
+    #[test]
+    fn denominators_are_rationalised() {
+        assert_eq!(exact("1/R(2, 2)").unwrap().to_string(), "√2/2");
+        assert_eq!(exact("1/R(3, 4)").unwrap().to_string(), "∛2/2");
+        assert_eq!(exact("1/(1 + R(2, 2))").unwrap().to_string(), "√2 - 1");
+    }
+
+    #[test]
+    fn rational_arithmetic_is_exact() {
+        assert_eq!(exact("0.1 + 0.2").unwrap().to_string(), "3/10");
+        assert_eq!(exact("(2/3)^(-2)").unwrap().to_string(), "9/4");
+        assert_eq!(exact("8/(2)^(2)").unwrap().to_string(), "2");
+        assert_eq!(
+            exact("7/2").unwrap().into_mixed_fraction().to_string(),
+            "3 1/2"
+        );
+        assert_eq!(exact("1_2 + 1_3").unwrap().to_string(), "5/6");
+    }
+
+    #[test]
+    fn angles_follow_the_same_rules_exactly() {
+        let mut environment = Environment::new();
+        environment.set_angle_unit(AngleUnit::Degrees);
+
+        let exact_in_degrees = |input: &str| {
+            let cluster = ErasableCluster::build(input).unwrap();
+            let expression = parse_into_expression(cluster.iter()).unwrap();
+
+            expression_to_exact(&expression, &environment).map(|exact| exact.to_string())
+        };
+
+        assert_eq!(exact_in_degrees("s(30)").unwrap(), "1/2");
+        assert_eq!(exact_in_degrees("30d + 15").unwrap(), "45 deg");
+        assert_eq!(exact_in_degrees("(p/2)r + 90").unwrap(), "π rad");
+        assert_eq!(exact_in_degrees("(90d)/((p/4)r)").unwrap(), "2");
+        assert_eq!(exact_in_degrees("2(30d)").unwrap(), "60 deg");
+        assert!(exact_in_degrees("30d(2d)").is_none());
+        assert!(exact_in_degrees("S(30d)").is_none());
+
+        assert_eq!(
+            exact("(p/2)r")
+                .unwrap()
+                .into_unit(AngleUnit::Gradians)
+                .unwrap()
+                .to_string(),
+            "100 grad"
+        );
+        assert_eq!(
+            exact("(p/2)r")
+                .unwrap()
+                .into_unit(AngleUnit::Turns)
+                .unwrap()
+                .to_string(),
+            "1/4 turn"
+        );
+        assert_eq!(exact_in_degrees("12D30'15\"").unwrap(), "3001/240 deg");
+    }
+
+    #[test]
+    fn incomplete_input_doesnt_panic() {
+        for input in [
+            "R(0, 2)",
+            "1_(2 + 3_4)",
+            "s(30d) + [(2)^(1_2)]",
+            "((((1))))",
+        ] {
+            for end in 1..=input.len() {
+                let cluster = ErasableCluster::build(&input[..end]).unwrap();
+
+                if let Ok(expression) = parse_into_expression(cluster.iter()) {
+                    expression_to_exact(&expression, &Environment::new());
+                }
+            }
+        }
+    }
+    #[test]
+    fn results_too_large_to_be_exact_fall_back_to_inexact() {
+        assert!(exact("(18446744073709551615)^(2)").is_none());
+        assert!(exact("18446744073709551615 + 18446744073709551615").is_none());
+    }
+}
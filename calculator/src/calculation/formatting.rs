@@ -0,0 +1,302 @@
+// how decimals are written out, eg: 1234.5 as 1,234.5 or 1.2345E3
+
+use super::calculation_precision::FloatingPointPrecison;
+
+/// Where the decimal point goes.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Notation {
+    /// eg: 1234.5
+    #[default]
+    Standard,
+    /// With one digit before the point, eg: 1.2345E3
+    Scientific,
+    /// With an exponent that's a multiple of 3, eg: 12.5E-6
+    Engineering,
+}
+
+impl Notation {
+    /// The notation after this one, which the session's is switched through.
+    pub fn next(self) -> Self {
+        match self {
+            Notation::Standard => Notation::Scientific,
+            Notation::Scientific => Notation::Engineering,
+            Notation::Engineering => Notation::Standard,
+        }
+    }
+}
+
+impl std::fmt::Display for Notation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Notation::Standard => "standard",
+            Notation::Scientific => "scientific",
+            Notation::Engineering => "engineering",
+        })
+    }
+}
+
+/// How many digits are shown.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Precision {
+    /// Just enough to tell the number apart from any other, eg: 0.30000000000000004
+    #[default]
+    Shortest,
+    /// Rounded to this many decimal places, eg: 3.14 to 2
+    DecimalPlaces(usize),
+    /// Rounded to this many significant figures, eg: 0.00314 to 3
+    SignificantFigures(usize),
+}
+
+/// How decimals are written. The default is how Rust writes them, eg:
+/// 0.30000000000000004 or 1000000000000000000000.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NumberFormat {
+    pub notation: Notation,
+    pub precision: Precision,
+    /// What the digits before the point are put in groups of three with (if
+    /// anything), eg: the commas of 1,234,567.
+    pub digit_grouping: Option<char>,
+    /// eg: the . of 3.14
+    pub decimal_separator: char,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            notation: Notation::default(),
+            precision: Precision::default(),
+            digit_grouping: None,
+            decimal_separator: '.',
+        }
+    }
+}
+
+// the digits of a number's shortest form (or rounded to a number of
+// significant figures), and the power of 10 of the first; eg: 12345 and 2 for
+// 123.45
+fn significant_digits(value: FloatingPointPrecison, figures: Option<usize>) -> (String, i32) {
+    let written = match figures {
+        Some(figures) => format!("{value:.*e}", figures.max(1) - 1),
+        None => format!("{value:e}"),
+    };
+
+    let (mantissa, exponent) = written
+        .split_once('e')
+        .expect("numbers are written with an exponent by {:e}");
+
+    (
+        mantissa.replace('.', ""),
+        exponent.parse().expect("the exponent is a whole number"),
+    )
+}
+
+// the digits split into a whole part and a fraction, with `point` digits
+// before the point; eg: 1 and 2345 for 12345 and 1
+fn place_point(digits: &str, point: i32) -> (String, String) {
+    let length = digits.len() as i32;
+
+    if point <= 0 {
+        let zeros = "0".repeat(-point as usize);
+        ("0".to_string(), format!("{zeros}{digits}"))
+    } else if point >= length {
+        let zeros = "0".repeat((point - length) as usize);
+        (format!("{digits}{zeros}"), String::new())
+    } else {
+        let (whole, fraction) = digits.split_at(point as usize);
+        (whole.to_string(), fraction.to_string())
+    }
+}
+
+impl NumberFormat {
+    /// Writes the number out, eg: 1234.5 as 1.23E3 in scientific notation to
+    /// 3 significant figures.
+    pub fn format(&self, value: FloatingPointPrecison) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+
+        let sign = if value.is_sign_negative() { "-" } else { "" };
+        let value = value.abs();
+
+        let (whole, fraction, exponent) = match (self.notation, self.precision) {
+            (Notation::Standard, Precision::Shortest) => {
+                let (whole, fraction) = split_at_point(value.to_string());
+                (whole, fraction, None)
+            }
+            (Notation::Standard, Precision::DecimalPlaces(places)) => {
+                let (whole, fraction) = split_at_point(format!("{value:.places$}"));
+                (whole, fraction, None)
+            }
+            (Notation::Standard, Precision::SignificantFigures(figures)) => {
+                let (digits, exponent) = significant_digits(value, Some(figures));
+                let (whole, fraction) = place_point(&digits, exponent + 1);
+                (whole, fraction, None)
+            }
+            (notation, precision) => {
+                // how many digits go before the point with the exponent
+                let before_point = |exponent: i32| match notation {
+                    Notation::Engineering => exponent.rem_euclid(3) + 1,
+                    _ => 1,
+                };
+
+                let figures = |exponent: i32| match precision {
+                    Precision::Shortest => None,
+                    Precision::DecimalPlaces(places) => {
+                        Some(places + before_point(exponent) as usize)
+                    }
+                    Precision::SignificantFigures(figures) => Some(figures),
+                };
+
+                let (_, estimate) = significant_digits(value, None);
+                let (mut digits, mut exponent) = significant_digits(value, figures(estimate));
+
+                // rounding can carry over into another digit, eg: 999.96 to
+                // 1 decimal place in engineering notation is 1.0E3, not 1000.0
+                if exponent != estimate {
+                    (digits, exponent) = significant_digits(value, figures(exponent));
+                }
+
+                let point = before_point(exponent);
+                let (whole, fraction) = place_point(&digits, point);
+
+                (whole, fraction, Some(exponent - point + 1))
+            }
+        };
+
+        let mut written = format!("{sign}{}", self.grouped(&whole));
+
+        if !fraction.is_empty() {
+            written.push(self.decimal_separator);
+            written.push_str(&fraction);
+        }
+
+        if let Some(exponent) = exponent {
+            written.push_str(&format!("E{exponent}"));
+        }
+
+        written
+    }
+
+    // eg: 1,234,567
+    fn grouped(&self, whole: &str) -> String {
+        let Some(separator) = self.digit_grouping else {
+            return whole.to_string();
+        };
+
+        let mut grouped = String::new();
+
+        for (i, digit) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i).is_multiple_of(3) {
+                grouped.push(separator);
+            }
+
+            grouped.push(digit);
+        }
+
+        grouped
+    }
+}
+
+// eg: 3 and 14 for 3.14
+fn split_at_point(written: String) -> (String, String) {
+    match written.split_once('.') {
+        Some((whole, fraction)) => (whole.to_string(), fraction.to_string()),
+        None => (written, String::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(notation: Notation, precision: Precision, value: FloatingPointPrecison) -> String {
+        NumberFormat {
+            notation,
+            precision,
+            ..NumberFormat::default()
+        }
+        .format(value)
+    }
+
+    #[test]
+    fn standard_notation_is_rounded() {
+        use Precision::*;
+
+        assert_eq!(
+            format(Notation::Standard, Shortest, 0.1 + 0.2),
+            "0.30000000000000004"
+        );
+        assert_eq!(
+            format(Notation::Standard, DecimalPlaces(2), 0.1 + 0.2),
+            "0.30"
+        );
+        assert_eq!(format(Notation::Standard, DecimalPlaces(0), -2.5), "-2");
+        assert_eq!(
+            format(Notation::Standard, SignificantFigures(3), 0.1 + 0.2),
+            "0.300"
+        );
+        assert_eq!(
+            format(Notation::Standard, SignificantFigures(2), 12345.0),
+            "12000"
+        );
+        assert_eq!(
+            format(Notation::Standard, SignificantFigures(2), 0.00314),
+            "0.0031"
+        );
+        assert_eq!(
+            format(Notation::Standard, SignificantFigures(3), 0.0),
+            "0.00"
+        );
+        assert_eq!(
+            format(Notation::Standard, Shortest, FloatingPointPrecison::NAN),
+            "NaN"
+        );
+    }
+
+    #[test]
+    fn exponents_are_used_in_scientific_and_engineering_notation() {
+        use Precision::*;
+
+        assert_eq!(format(Notation::Scientific, Shortest, 1e21), "1E21");
+        assert_eq!(format(Notation::Scientific, Shortest, -1234.5), "-1.2345E3");
+        assert_eq!(
+            format(Notation::Scientific, DecimalPlaces(2), 0.00314159),
+            "3.14E-3"
+        );
+        assert_eq!(
+            format(Notation::Scientific, SignificantFigures(2), 9.99),
+            "1.0E1"
+        );
+        assert_eq!(format(Notation::Engineering, Shortest, 12345.0), "12.345E3");
+        assert_eq!(
+            format(Notation::Engineering, Shortest, 0.0000125),
+            "12.5E-6"
+        );
+        assert_eq!(format(Notation::Engineering, Shortest, 100000.0), "100E3");
+        assert_eq!(
+            format(Notation::Engineering, SignificantFigures(3), 12345.0),
+            "12.3E3"
+        );
+        assert_eq!(
+            format(Notation::Engineering, DecimalPlaces(1), 999.96),
+            "1.0E3"
+        );
+        assert_eq!(
+            format(Notation::Engineering, DecimalPlaces(1), 0.0),
+            "0.0E0"
+        );
+    }
+
+    #[test]
+    fn digits_can_be_grouped_and_separated() {
+        let format = NumberFormat {
+            digit_grouping: Some(' '),
+            decimal_separator: ',',
+            ..NumberFormat::default()
+        };
+
+        assert_eq!(format.format(1234567.5), "1 234 567,5");
+        assert_eq!(format.format(-123.25), "-123,25");
+        assert_eq!(format.format(1000.0), "1 000");
+    }
+}
@@ -1,33 +1,63 @@
 use super::calculation_precision::UnsignedValuePrecision;
 
-// pub fn lcm(a: RationalNumberPartDepth, b: RationalNumberPartDepth) -> RationalNumberPartDepth {
-//     let (mut x, mut y) = if a > b { (a, b) } else { (b, a) };
-//     let mut rem: RationalNumberPartDepth = x % y;
+pub fn hcf(mut a: UnsignedValuePrecision, mut b: UnsignedValuePrecision) -> UnsignedValuePrecision {
+    // euclid's algorithm; repeated subtraction is far too slow for large values
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
 
-//     while rem != 0 {
-//         x = y;
-//         y = rem;
-//         rem = x % y;
-//     }
+pub fn lcm(a: UnsignedValuePrecision, b: UnsignedValuePrecision) -> Option<UnsignedValuePrecision> {
+    (a / hcf(a, b)).checked_mul(b)
+}
 
-//     let lcm = a * b / y;
-//     lcm
-// }
+/// The integer `k`th root of `n`, if `n` is a perfect `k`th power.
+pub fn exact_integer_root(n: UnsignedValuePrecision, k: u32) -> Option<UnsignedValuePrecision> {
+    let estimate = (n as f64).powf(1.0 / k as f64).round() as UnsignedValuePrecision;
 
-pub fn hcf(mut a: UnsignedValuePrecision, mut b: UnsignedValuePrecision) -> UnsignedValuePrecision {
-    while a != b && a > 0 && b > 0 {
-        if a > b {
-            a -= b;
-        } else {
-            b -= a;
+    // the floating point estimate can be off by one for large values
+    (estimate.saturating_sub(1)..=estimate.saturating_add(1))
+        .find(|root| root.checked_pow(k) == Some(n))
+}
+
+// trial division beyond this gets noticeably slow
+const TRIAL_DIVISION_LIMIT: UnsignedValuePrecision = 1_000_000;
+
+/// Factorises `n` into (prime, multiplicity) pairs, in ascending order.
+///
+/// Factors above the trial division limit are only found if what is left over is
+/// a prime or the square or cube of one; otherwise the leftover is treated as a
+/// single (possibly composite) factor.
+pub fn prime_factors(mut n: UnsignedValuePrecision) -> Vec<(UnsignedValuePrecision, u32)> {
+    let mut factors = vec![];
+    let mut factor: UnsignedValuePrecision = 2;
+
+    while factor <= TRIAL_DIVISION_LIMIT && factor.saturating_mul(factor) <= n {
+        let mut multiplicity = 0;
+
+        while n.is_multiple_of(factor) {
+            n /= factor;
+            multiplicity += 1;
         }
+
+        if multiplicity > 0 {
+            factors.push((factor, multiplicity));
+        }
+
+        factor += if factor == 2 { 1 } else { 2 };
     }
 
-    if a == 0 {
-        b
-    } else {
-        a
+    if n > 1 {
+        let perfect_power = [3, 2]
+            .into_iter()
+            .find_map(|k| exact_integer_root(n, k).map(|root| (root, k)));
+
+        factors.push(perfect_power.unwrap_or((n, 1)));
     }
+
+    factors
 }
 
 #[cfg(test)]
@@ -38,5 +68,21 @@ mod tests {
     fn hcf_works() {
         assert_eq!(hcf(5, 7), 1);
         assert_eq!(hcf(50, 60), 10);
+        assert_eq!(hcf(0, 4), 4);
+    }
+
+    #[test]
+    fn lcm_works() {
+        assert_eq!(lcm(4, 6), Some(12));
+        assert_eq!(lcm(3, 1), Some(3));
+    }
+
+    #[test]
+    fn prime_factorisation_works() {
+        assert_eq!(prime_factors(54), vec![(2, 1), (3, 3)]);
+        assert_eq!(prime_factors(97), vec![(97, 1)]);
+        assert_eq!(prime_factors(1), vec![]);
+        // a square of a prime beyond the trial division limit
+        assert_eq!(prime_factors(1_000_003 * 1_000_003), vec![(1_000_003, 2)]);
     }
 }
@@ -1,90 +1,406 @@
-// contains Into<Inexact> implementations and Inexact definition
+// contains the inexact evaluation of expressions and the Inexact definition
 
 use std::{
     f64::consts::{E, PI},
     ops::Mul,
 };
 
-use crate::{input_parsing::erasable::Erasable, shared::errors::CalculationError};
+use crate::shared::{errors::CalculationError, sign::Sign};
 
 use super::{
-    calculation_precision::FloatingPointPrecison,
+    calculation_precision::{FloatingPointPrecison, UnsignedValuePrecision, WideValuePrecision},
     calculator::{
         AngleUnit, Expression, Function, MultipliedOrDivided, NamedConstant, Term, TermFragment,
         TermFragmentMagnitude, UnnamedConstant,
     },
+    environment::{Environment, Value},
+    exact::Exact,
+    formatting::{NumberFormat, Precision},
+    integer::{bitwise_to_integer, expression_to_integer},
+    units::{exponent_too_large, Dimensions, Unit},
     CalculationResult,
 };
 
+// a number, an angle (which is measured in radians, see Dimensions) or
+// another quantity. Angles follow these rules:
+// - an angle times or divided by a number is an angle
+// - angles can be added to each other, and a number added to an angle is
+//   taken to be in the session's angle unit
+// - an angle divided by an angle is a number
+// - angles can't be multiplied together (or raised to powers), and numbers
+//   can't be divided by them
+// The value is in SI units (eg: 5 km is 5000 m), except that an angle is kept
+// in the unit it was given in (eg: 30deg stays 30 of deg), and only quantities
+// with the same dimensions can be added together.
+// A number of degrees Celsius or Fahrenheit (eg: 100 °F) is a reading, so
+// counts from the scale's zero rather than absolute zero, but in other units
+// (eg: °F/s) only the size of a degree counts. A reading can have a
+// difference added to it or taken from it (eg: 20 °C + 5 K), and one reading
+// taken from another gives a difference, but two readings can't be added
+#[derive(Clone, Debug)]
 pub struct Inexact {
     value: FloatingPointPrecison,
-    unit: Option<AngleUnit>,
+    dimensions: Dimensions,
+    // what the angle is in, if the dimensions have one
+    angle_unit: AngleUnit,
+    is_reading: bool,
+    // only for showing it, eg: 12°30'15" rather than 12.504166666666666 deg
+    in_degrees_minutes_seconds: bool,
 }
 
 impl std::fmt::Display for Inexact {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let unit = match self.unit {
-            Some(unit) => match unit {
-                AngleUnit::Degrees => Erasable::Degrees.into(),
-                AngleUnit::Radians => Erasable::Radians.into(),
-            },
-            None => "",
+        // eg: {:.2} rounds to 2 decimal places
+        let number_format = NumberFormat {
+            precision: f
+                .precision()
+                .map_or(Precision::Shortest, Precision::DecimalPlaces),
+            ..NumberFormat::default()
         };
-        let value = self.value;
-
-        let separator = if self.unit.is_some() { " " } else { "" };
 
-        format!("{value}{separator}{unit}").fmt(f)
+        f.write_str(&self.format(&number_format))
     }
 }
 
 impl Inexact {
-    pub fn into_radians(self) -> Self {
-        if self.unit.is_some() && self.unit.unwrap() == AngleUnit::Degrees {
-            Inexact {
-                unit: Some(AngleUnit::Radians),
-                value: self.value.to_radians(),
-            }
-        } else {
-            self
+    /// The angle in the given unit; numbers without a unit are left as they
+    /// are.
+    pub fn into_unit(self, unit: AngleUnit) -> Self {
+        if !self.is_angle() || self.angle_unit == unit {
+            return self;
+        }
+
+        Inexact {
+            value: unit.convert_radians(self.angle_unit.to_radians(self.value)),
+            angle_unit: unit,
+            ..self
         }
     }
 
+    pub fn into_radians(self) -> Self {
+        self.into_unit(AngleUnit::Radians)
+    }
+
     pub fn into_degrees(self) -> Self {
-        if self.unit.is_some() && self.unit.unwrap() == AngleUnit::Radians {
-            Inexact {
-                unit: Some(AngleUnit::Degrees),
-                value: self.value.to_degrees(),
-            }
-        } else {
-            self
+        self.into_unit(AngleUnit::Degrees)
+    }
+
+    /// The angle in degrees, shown in degrees, minutes and seconds (eg:
+    /// 12°30'15"). Numbers without a unit are left as they are.
+    pub fn into_degrees_minutes_seconds(self) -> Self {
+        match self.dimensions {
+            Dimensions::ANGLE => Inexact {
+                in_degrees_minutes_seconds: true,
+                ..self.into_degrees()
+            },
+            _ => self,
         }
     }
 
-    pub fn is_nan(&self) -> bool {
-        self.value.is_nan()
+    /// The value if it's a whole number (without units) that fits.
+    pub(crate) fn as_integer(&self) -> Option<WideValuePrecision> {
+        let is_whole = self.value.fract() == 0.0
+            && self.value.abs() <= UnsignedValuePrecision::MAX as FloatingPointPrecison;
+
+        (self.dimensions.is_none() && is_whole).then_some(self.value as WideValuePrecision)
     }
-}
-impl Mul<Inexact> for Inexact {
-    type Output = Inexact;
 
-    fn mul(mut self, mut rhs: Inexact) -> Self::Output {
-        self = self.into_radians();
-        rhs = rhs.into_radians();
+    /// Whether it's a quantity or an angle rather than a number, eg: 5 km or
+    /// 30deg.
+    pub(crate) fn has_units(&self) -> bool {
+        !self.dimensions.is_none()
+    }
+
+    fn is_angle(&self) -> bool {
+        self.dimensions.angle_power() != 0
+    }
+
+    // eg: deg, m/s or rad/s, and empty for numbers
+    fn units(&self) -> String {
+        if !self.is_angle() {
+            return self.dimensions.to_string();
+        }
+
+        // the angle goes first, in its own unit
+        let unit = self.angle_unit.symbol();
+        let dimensions = self.dimensions.with_angle_power(0).to_string();
+
+        match dimensions.strip_prefix('1') {
+            _ if dimensions.is_empty() => unit.to_string(),
+            // eg: rad/s rather than rad 1/s
+            Some(below) => format!("{unit}{below}"),
+            None => format!("{unit} {dimensions}"),
+        }
+    }
+
+    // what it is, for errors
+    fn described(&self) -> String {
+        match self.units() {
+            units if units.is_empty() => "a number".to_string(),
+            units => units,
+        }
+    }
+
+    /// How many of `units` (eg: km/h) the quantity is. A number converted
+    /// into an angle unit is taken to be in the session's unit.
+    pub(crate) fn in_units_of(
+        self,
+        units: &Expression,
+        environment: &Environment,
+    ) -> Result<FloatingPointPrecison, CalculationError> {
+        let offset = scale_offset(units);
+        let units = expression_to_inexact(units, environment)?;
+
+        let quantity = match units.is_angle() {
+            true => self.into_angle(environment.angle_unit()),
+            false => self,
+        };
+
+        if quantity.dimensions != units.dimensions {
+            return Err(CalculationError::new(format!(
+                "can't convert {} into {}",
+                quantity.described(),
+                units.described()
+            )));
+        }
+
+        Ok((quantity.into_radians().value - offset) / units.into_radians().value)
+    }
+
+    // the seconds are rounded to the formatter's precision, or to at most 2
+    // decimal places without the trailing zeros
+    /// The number written out in the format, followed by its units.
+    pub fn format(&self, number_format: &NumberFormat) -> String {
+        if self.in_degrees_minutes_seconds && self.value.is_finite() {
+            return self.format_degrees_minutes_seconds(number_format);
+        }
+
+        let unit = self.units();
+        let value = number_format.format(self.value);
+        let separator = if unit.is_empty() { "" } else { " " };
+
+        format!("{value}{separator}{unit}")
+    }
+
+    // the seconds are rounded to the format's decimal places (or 2), and
+    // otherwise written as they are
+    fn format_degrees_minutes_seconds(&self, number_format: &NumberFormat) -> String {
+        let rounded_places = match number_format.precision {
+            Precision::DecimalPlaces(places) => Some(places),
+            _ => None,
+        };
+
+        let places = rounded_places.unwrap_or(2);
+        let scale = 10f64.powi(places as i32);
+        // rounding the total first carries eg: 59.999" over into the minutes
+        let total_seconds = (self.value.abs() * 3600.0 * scale).round() / scale;
+
+        let degrees = (total_seconds / 3600.0).trunc();
+        let minutes = ((total_seconds - degrees * 3600.0) / 60.0).trunc();
+        let seconds = total_seconds - degrees * 3600.0 - minutes * 60.0;
+
+        let mut seconds = format!("{seconds:.places$}");
+        if rounded_places.is_none() && seconds.contains('.') {
+            seconds = seconds
+                .trim_end_matches('0')
+                .trim_end_matches('.')
+                .to_string();
+        }
+        let seconds = seconds.replace('.', &number_format.decimal_separator.to_string());
 
-        let unit = if let None = self.unit {
-            // if this is None, both would be None so it's fine.
-            rhs.unit
+        let sign = if self.value < 0.0 && total_seconds != 0.0 {
+            "-"
         } else {
-            self.unit
+            ""
         };
 
+        format!("{sign}{degrees}°{minutes}'{seconds}\"")
+    }
+
+    pub fn is_nan(&self) -> bool {
+        self.value.is_nan()
+    }
+
+    // a number without a unit is taken to be in the session's angle unit
+    fn into_angle(self, session_unit: AngleUnit) -> Self {
+        if self.is_angle() {
+            return self;
+        }
+
         Inexact {
-            unit,
+            dimensions: self.dimensions.with_angle_power(1),
+            angle_unit: session_unit,
+            ..self
+        }
+    }
+
+    // the value, which mustn't be an angle or have units
+    fn number(self) -> Result<FloatingPointPrecison, CalculationError> {
+        if self.is_angle() {
+            return Err(CalculationError::new(
+                "expected a number but found an angle".to_string(),
+            ));
+        }
+
+        match self.dimensions.is_none() {
+            true => Ok(self.value),
+            false => Err(CalculationError::new(format!(
+                "expected a number but found {}",
+                self.dimensions
+            ))),
+        }
+    }
+
+    // `is_subtracted` if the rhs is being taken away, eg: the 5 °C of
+    // 10 °C - 5 °C
+    fn checked_add(
+        self,
+        rhs: Inexact,
+        is_subtracted: bool,
+        session_unit: AngleUnit,
+    ) -> CalculationResult {
+        // a number added to an angle is taken to be in the session's unit (so
+        // is eg: 1/s added to rad/s)
+        let (lhs, rhs) = match (self.is_angle(), rhs.is_angle()) {
+            (true, false) if self.dimensions.with_angle_power(0) == rhs.dimensions => {
+                (self, rhs.into_angle(session_unit))
+            }
+            (false, true) if rhs.dimensions.with_angle_power(0) == self.dimensions => {
+                (self.into_angle(session_unit), rhs)
+            }
+            _ => (self, rhs),
+        };
+
+        if lhs.dimensions != rhs.dimensions {
+            return Err(CalculationError::new(format!(
+                "can't add {} to {}",
+                rhs.described(),
+                lhs.described()
+            )));
+        }
+
+        let is_reading = match (lhs.is_reading, rhs.is_reading) {
+            (true, true) if !is_subtracted => {
+                return Err(CalculationError::new(
+                    "can't add two temperature readings (add a difference in K instead)"
+                        .to_string(),
+                ))
+            }
+            (true, true) => false,
+            (lhs, rhs) => lhs || rhs,
+        };
+
+        // in the unit of the angle on the left
+        let rhs = rhs.into_unit(lhs.angle_unit);
+
+        Ok(Inexact {
+            value: lhs.value + rhs.value,
+            is_reading,
+            ..lhs
+        })
+    }
+
+    fn checked_mul(self, rhs: Inexact) -> CalculationResult {
+        if self.is_angle() && rhs.is_angle() {
+            return Err(CalculationError::new(
+                "angles can't be multiplied together".to_string(),
+            ));
+        }
+
+        let angle_unit = match self.is_angle() {
+            true => self.angle_unit,
+            false => rhs.angle_unit,
+        };
+
+        Ok(Inexact {
             value: self.value * rhs.value,
+            dimensions: self.dimensions.times(rhs.dimensions)?,
+            angle_unit,
+            is_reading: false,
+            ..self
+        })
+    }
+
+    fn checked_div(self, rhs: Inexact) -> CalculationResult {
+        let dimensions = self.dimensions.divided_by(rhs.dimensions)?;
+
+        match (self.is_angle(), rhs.is_angle()) {
+            (true, true) => Ok(Inexact {
+                dimensions,
+                ..number(self.into_radians().value / rhs.into_radians().value)
+            }),
+            (false, true) => Err(CalculationError::new(
+                "a number can't be divided by an angle".to_string(),
+            )),
+            (_, false) => Ok(Inexact {
+                value: self.value / rhs.value,
+                dimensions,
+                is_reading: false,
+                ..self
+            }),
+        }
+    }
+
+    // quantities can only be raised to whole powers, eg: m^2
+    fn checked_pow(self, exponent: FloatingPointPrecison) -> CalculationResult {
+        if self.is_angle() || self.dimensions.is_none() {
+            return Ok(number(self.number()?.powf(exponent)));
+        }
+
+        if exponent.fract() != 0.0 {
+            return Err(CalculationError::new(format!(
+                "{} can only be raised to whole powers",
+                self.dimensions
+            )));
+        }
+
+        // `as` saturates, so huge powers are still too large rather than wrapping
+        let whole = i8::try_from(exponent as i64).map_err(|_| exponent_too_large())?;
+
+        Ok(Inexact {
+            value: self.value.powf(exponent),
+            dimensions: self.dimensions.pow(whole)?,
+            is_reading: false,
+            ..self
+        })
+    }
+
+    // eg: the square root of m^2 is m, but m^3 has none
+    fn checked_root(self, degree: FloatingPointPrecison) -> CalculationResult {
+        if self.is_angle() || self.dimensions.is_none() {
+            return Ok(number(self.number()?.powf(1.0 / degree)));
+        }
+
+        let dimensions = i8::try_from(degree as i64)
+            .ok()
+            .filter(|whole| FloatingPointPrecison::from(*whole) == degree)
+            .and_then(|whole| self.dimensions.root(whole));
+
+        let Some(dimensions) = dimensions else {
+            return Err(CalculationError::new(format!(
+                "{} has no root of degree {degree}",
+                self.dimensions
+            )));
+        };
+
+        Ok(Inexact {
+            value: self.value.powf(1.0 / degree),
+            dimensions,
+            is_reading: false,
+            ..self
+        })
+    }
+}
+// the decimal approximation, eg: 0.7071067811865476 for √2/2
+impl From<&Exact> for Inexact {
+    fn from(exact: &Exact) -> Self {
+        match exact.angle_unit() {
+            Some(unit) => angle(exact.to_float(), unit),
+            None => number(exact.to_float()),
         }
     }
 }
+
 impl Mul<FloatingPointPrecison> for Inexact {
     type Output = Inexact;
 
@@ -93,200 +409,300 @@ impl Mul<FloatingPointPrecison> for Inexact {
         self
     }
 }
-impl core::ops::Add<Inexact> for Inexact {
-    type Output = Inexact;
 
-    fn add(mut self, mut rhs: Inexact) -> Self::Output {
-        self = self.into_radians();
-        rhs = rhs.into_radians();
+/// `value` of `units` (eg: 100 of °F), as a quantity.
+pub(crate) fn quantity_in(
+    value: FloatingPointPrecison,
+    units: &Expression,
+    environment: &Environment,
+) -> CalculationResult {
+    let mut quantity = number(value).checked_mul(expression_to_inexact(units, environment)?)?;
+    quantity.value += scale_offset(units);
 
-        Inexact {
-            unit: if self.unit.is_some() {
-                self.unit
-            } else {
-                rhs.unit
-            },
-            value: self.value + rhs.value,
+    Ok(quantity)
+}
+
+// the zero of a temperature scale, in SI units, if the fragment is one (eg:
+// 273.15 K for the °C of 100 °C), with what the scale measures
+fn reading_offset(fragment: &TermFragment) -> Option<(FloatingPointPrecison, Dimensions)> {
+    match fragment {
+        TermFragment {
+            sign: Sign::Positive,
+            fragment_magnitude:
+                TermFragmentMagnitude::Unit(Unit {
+                    offset, dimensions, ..
+                }),
+            multiplied_or_divided,
+            ..
+        } if *offset != 0.0 && !matches!(multiplied_or_divided, MultipliedOrDivided::Divided) => {
+            Some((*offset, *dimensions))
         }
+        _ => None,
     }
 }
 
-impl Into<CalculationResult> for &Term {
-    fn into(self) -> CalculationResult {
-        let mut result = None;
+// the zero of the scale when the units are just one (eg: °C), else 0
+fn scale_offset(units: &Expression) -> FloatingPointPrecison {
+    let offset = match units.as_slice() {
+        [Term { fragments }] => match fragments.as_slice() {
+            [fragment] => reading_offset(fragment),
+            _ => None,
+        },
+        _ => None,
+    };
 
-        for fragment in &self.fragments {
-            let inexact: CalculationResult = fragment.into();
-            let inexact = inexact?;
+    offset.map_or(0.0, |(offset, _)| offset)
+}
 
-            match result {
-                Some(product) => result = Some(product * inexact),
-                None => result = Some(inexact),
+// `is_subtracted` as in Inexact::checked_add
+fn term_to_inexact(
+    term: &Term,
+    is_subtracted: bool,
+    environment: &Environment,
+) -> CalculationResult {
+    let mut result: Option<Inexact> = None;
+
+    for fragment in &term.fragments {
+        let inexact = fragment_to_inexact(fragment, environment)?;
+
+        result = Some(match (result, fragment.multiplied_or_divided) {
+            (Some(product), MultipliedOrDivided::Divided) => product.checked_div(inexact)?,
+            (Some(product), _) => product.checked_mul(inexact)?,
+            (None, MultipliedOrDivided::Divided) => number(1.0).checked_div(inexact)?,
+            (None, _) => inexact,
+        });
+    }
+
+    // a reading, eg: 100 °F, but not °F/s (which isn't last) or 100 °F^2.
+    // Taking one away takes away its zero too
+    if let (Some(result), [_, .., last]) = (&mut result, term.fragments.as_slice()) {
+        if let Some((offset, dimensions)) = reading_offset(last) {
+            if result.dimensions == dimensions {
+                result.value += if is_subtracted { -offset } else { offset };
+                result.is_reading = true;
             }
         }
+    }
 
-        // match result {
-        //     Some(result) => Ok(result),
-        //     None => Err(),
-        // }
+    result.ok_or(CalculationError::new("unexpected empty term".to_string()))
+}
 
-        result.ok_or(CalculationError::new("unexpected empty term".to_string()))
+fn number(value: FloatingPointPrecison) -> Inexact {
+    Inexact {
+        value,
+        dimensions: Dimensions::default(),
+        angle_unit: AngleUnit::default(),
+        is_reading: false,
+        in_degrees_minutes_seconds: false,
     }
 }
 
-impl Into<CalculationResult> for &TermFragment {
-    fn into(self) -> CalculationResult {
-        let magnitude: CalculationResult = (&self.fragment_magnitude).into();
-        let magnitude = magnitude?;
+fn angle(value: FloatingPointPrecison, unit: AngleUnit) -> Inexact {
+    number(value).into_angle(unit)
+}
 
-        let multiplier = self.sign as isize as FloatingPointPrecison;
+pub(crate) fn fragment_to_inexact(
+    fragment: &TermFragment,
+    environment: &Environment,
+) -> CalculationResult {
+    let magnitude = magnitude_to_inexact(&fragment.fragment_magnitude, environment)?;
 
-        let mut magnitude = magnitude * multiplier;
+    let multiplier = fragment.sign as isize as FloatingPointPrecison;
 
-        // preserve unit
-        magnitude.unit = if magnitude.unit.is_some() {
-            magnitude.unit
-        } else {
-            self.angle_unit
-        };
+    let magnitude = magnitude * multiplier;
 
-        match self.multiplied_or_divided {
-            MultipliedOrDivided::Divided => {
-                magnitude.value = 1.0 / magnitude.value;
-                Ok(magnitude)
-            }
-            _ => Ok(magnitude),
-        }
-    }
+    // preserve unit
+    Ok(match fragment.angle_unit {
+        Some(unit) => magnitude.into_angle(unit),
+        None => magnitude,
+    })
 }
 
-fn expression_to_radians_if_possible(expression: &Expression) -> CalculationResult {
-    let mut angle = expression_to_inexact(&expression)?;
+// the angle in radians, with numbers taken to be in the session's unit
+fn expression_to_radians(expression: &Expression, environment: &Environment) -> CalculationResult {
+    let angle = expression_to_inexact(expression, environment)?;
 
-    if angle.unit.is_some() && AngleUnit::Degrees == angle.unit.unwrap() {
-        angle.value = angle.value.to_radians();
+    if !angle.dimensions.with_angle_power(0).is_none() {
+        return Err(CalculationError::new(format!(
+            "expected an angle but found {}",
+            angle.described()
+        )));
     }
 
-    Ok(angle)
+    Ok(angle.into_angle(environment.angle_unit()).into_radians())
 }
-impl Into<CalculationResult> for &TermFragmentMagnitude {
-    fn into(self) -> CalculationResult {
-        match self {
-            TermFragmentMagnitude::Bracket(expression) => expression_to_inexact(&expression),
-            TermFragmentMagnitude::Function(function) => function.into(),
-            TermFragmentMagnitude::NamedConstant {
-                coefficient,
-                constant,
-            } => {
-                let coefficient = expression_to_inexact(&coefficient)?;
-                match constant {
-                    NamedConstant::E => Ok(coefficient * E),
-                    NamedConstant::Pi => Ok(coefficient * PI),
-                }
-            }
-            TermFragmentMagnitude::NonNamedConstant(constant) => match constant {
-                UnnamedConstant::Decimal {
-                    before_decimal_point,
-                    after_decimal_point,
-                } => {
-                    let value = format!("{}.{}", before_decimal_point, after_decimal_point)
-                        .parse::<FloatingPointPrecison>();
-
-                    match value {
-                        Ok(value) => Ok(Inexact { value, unit: None }),
-                        Err(err) => Err(CalculationError::new(err.to_string())),
-                    }
-                }
-                UnnamedConstant::Fraction {
-                    numerator,
-                    denominator,
-                } => {
-                    let numerator = expression_to_radians_if_possible(&numerator)?;
-                    let denominator = expression_to_radians_if_possible(&denominator)?;
-
-                    Ok(Inexact {
-                        value: numerator.value / denominator.value,
-                        unit: numerator.unit,
-                    })
-                }
-                UnnamedConstant::Integer(value) => Ok(Inexact {
-                    value: *value as FloatingPointPrecison,
-                    unit: None,
-                }),
-                UnnamedConstant::Power { base, exponent } => {
-                    let base = expression_to_inexact(&base)?;
-                    let exponent = expression_to_inexact(&exponent)?;
 
-                    let value = base.value.powf(exponent.value);
+fn expression_to_number(
+    expression: &Expression,
+    environment: &Environment,
+) -> Result<FloatingPointPrecison, CalculationError> {
+    expression_to_inexact(expression, environment)?.number()
+}
 
-                    Ok(Inexact {
-                        unit: base.unit,
-                        value,
-                    })
-                }
+fn magnitude_to_inexact(
+    magnitude: &TermFragmentMagnitude,
+    environment: &Environment,
+) -> CalculationResult {
+    match magnitude {
+        TermFragmentMagnitude::Bracket(expression) => {
+            expression_to_inexact(expression, environment)
+        }
+        TermFragmentMagnitude::Function(function) => function_to_inexact(function, environment),
+        TermFragmentMagnitude::Variable(variable) => Ok(environment.get(variable)?.inexact.clone()),
+        TermFragmentMagnitude::Bitwise(operation) => {
+            Ok(number(
+                bitwise_to_integer(operation, environment)? as FloatingPointPrecison
+            ))
+        }
+        TermFragmentMagnitude::Unit(unit) => Ok(match unit.angle_unit() {
+            // kept in its own unit, eg: 1 of deg
+            Some(angle_unit) => angle(1.0, angle_unit),
+            None => Inexact {
+                dimensions: unit.dimensions,
+                ..number(unit.factor)
             },
+        }),
+        TermFragmentMagnitude::NamedConstant {
+            coefficient,
+            constant,
+        } => {
+            let coefficient = expression_to_inexact(coefficient, environment)?;
+            match constant {
+                NamedConstant::E => Ok(coefficient * E),
+                NamedConstant::Pi => Ok(coefficient * PI),
+                NamedConstant::Answer(index) => {
+                    coefficient.checked_mul(environment.answer(*index)?.inexact.clone())
+                }
+            }
         }
+        TermFragmentMagnitude::NonNamedConstant(constant) => match constant {
+            UnnamedConstant::Decimal {
+                before_decimal_point,
+                after_decimal_point,
+            } => {
+                let value = format!("{}.{}", before_decimal_point, after_decimal_point)
+                    .parse::<FloatingPointPrecison>();
+
+                match value {
+                    Ok(value) => Ok(number(value)),
+                    Err(err) => Err(CalculationError::new(err.to_string())),
+                }
+            }
+            UnnamedConstant::Fraction {
+                numerator,
+                denominator,
+            } => {
+                let numerator = expression_to_inexact(numerator, environment)?;
+                let denominator = expression_to_inexact(denominator, environment)?;
+
+                numerator.checked_div(denominator)
+            }
+            UnnamedConstant::Integer(value)
+            | UnnamedConstant::Hexadecimal(value)
+            | UnnamedConstant::Binary(value)
+            | UnnamedConstant::Octal(value) => Ok(number(*value as FloatingPointPrecison)),
+            UnnamedConstant::Power { base, exponent } => {
+                let base = expression_to_inexact(base, environment)?;
+                let exponent = expression_to_number(exponent, environment)?;
+
+                base.checked_pow(exponent)
+            }
+        },
     }
 }
 
-impl Into<CalculationResult> for &Function {
-    fn into(self) -> CalculationResult {
-        match self {
-            Function::Absolute(expression) => {
-                let mut inexact = expression_to_inexact(&expression)?;
-                inexact.value = inexact.value.abs();
-                Ok(inexact)
+fn function_to_inexact(function: &Function, environment: &Environment) -> CalculationResult {
+    match function {
+        Function::Absolute(expression) => {
+            let mut inexact = expression_to_inexact(expression, environment)?;
+            inexact.value = inexact.value.abs();
+            Ok(inexact)
+        }
+        Function::NthRoot(degree, under_the_root) => {
+            let degree = expression_to_number(degree, environment)?;
+            let under_the_root = expression_to_inexact(under_the_root, environment)?;
+
+            under_the_root.checked_root(degree)
+        }
+        Function::Sin(expression) => Ok(number(
+            expression_to_radians(expression, environment)?.value.sin(),
+        )),
+        Function::Cos(expression) => Ok(number(
+            expression_to_radians(expression, environment)?.value.cos(),
+        )),
+        Function::Tan(expression) => Ok(number(
+            expression_to_radians(expression, environment)?.value.tan(),
+        )),
+        Function::Arcsin(expression) => Ok(angle(
+            expression_to_number(expression, environment)?.asin(),
+            AngleUnit::Radians,
+        )),
+        Function::Arccos(expression) => Ok(angle(
+            expression_to_number(expression, environment)?.acos(),
+            AngleUnit::Radians,
+        )),
+        Function::Arctan(expression) => Ok(angle(
+            expression_to_number(expression, environment)?.atan(),
+            AngleUnit::Radians,
+        )),
+        Function::User { name, arguments } => {
+            if !environment.is_function(name) {
+                // not a function, so eg: x(2) is x times 2
+                let [argument] = arguments.as_slice() else {
+                    return Err(CalculationError::new(format!("unknown function {name}")));
+                };
+
+                let variable = environment.get(name)?.inexact.clone();
+                return variable.checked_mul(expression_to_inexact(argument, environment)?);
             }
-            Function::NthRoot(degree, under_the_root) => {
-                let degree = expression_to_inexact(&degree)?;
-                let under_the_root = expression_to_inexact(&under_the_root)?;
 
-                Ok(Inexact {
-                    unit: under_the_root.unit,
-                    value: under_the_root.value.powf(1.0 / degree.value),
+            let arguments = arguments
+                .iter()
+                .map(|argument| {
+                    Ok(Value {
+                        // only the inexact value is needed here
+                        exact: None,
+                        inexact: expression_to_inexact(argument, environment)?,
+                    })
                 })
-            }
-            Function::Sin(expression) => Ok(Inexact {
-                unit: None,
-                value: expression_to_radians_if_possible(&expression)?.value.sin(),
-            }),
-            Function::Cos(expression) => Ok(Inexact {
-                unit: None,
-                value: expression_to_radians_if_possible(&expression)?.value.cos(),
-            }),
-            Function::Tan(expression) => Ok(Inexact {
-                unit: None,
-                value: expression_to_radians_if_possible(&expression)?.value.tan(),
-            }),
-            Function::Arcsin(expression) => Ok(Inexact {
-                unit: Some(AngleUnit::Radians),
-                value: expression_to_inexact(&expression)?.value.asin(),
-            }),
-            Function::Arccos(expression) => Ok(Inexact {
-                unit: Some(AngleUnit::Radians),
-                value: expression_to_inexact(&expression)?.value.acos(),
-            }),
-            Function::Arctan(expression) => Ok(Inexact {
-                unit: Some(AngleUnit::Radians),
-                value: expression_to_inexact(&expression)?.value.atan(),
-            }),
+                .collect::<Result<Vec<_>, CalculationError>>()?;
+
+            let (function, environment) = environment.call(name, arguments)?;
+            expression_to_inexact(&function.body, &environment)
         }
     }
 }
 
-pub(crate) fn expression_to_inexact(expression: &Expression) -> CalculationResult {
-    let mut sum = None;
+pub(crate) fn expression_to_inexact(
+    expression: &Expression,
+    environment: &Environment,
+) -> CalculationResult {
+    // everything is a whole number in programmer mode
+    if environment.word_size().is_some() {
+        let integer = expression_to_integer(expression, environment)?;
+        return Ok(number(integer as FloatingPointPrecison));
+    }
+
+    let mut sum: Option<Inexact> = None;
 
     if expression.is_empty() {
         return Err(CalculationError::new("empty expression".to_string()));
     }
 
-    for term in expression {
-        let term: CalculationResult = term.into();
-        let term = term?;
+    for (index, term) in expression.iter().enumerate() {
+        // eg: the - 5 °C of 10 °C - 5 °C, but not the -5 °C of -5 °C + 10 K
+        let is_subtracted = index > 0
+            && term
+                .fragments
+                .first()
+                .is_some_and(|fragment| fragment.sign == Sign::Negative);
+
+        let term = term_to_inexact(term, is_subtracted, environment)?;
 
         match sum {
-            Some(prev) => sum = Some(prev + term),
+            Some(prev) => {
+                sum = Some(prev.checked_add(term, is_subtracted, environment.angle_unit())?)
+            }
             None => sum = Some(term),
         }
     }
@@ -298,32 +714,189 @@ pub(crate) fn expression_to_inexact(expression: &Expression) -> CalculationResul
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use crate::{
-        calculation::calculator::{AngleUnit, Calculator},
+        calculation::{calculator::Calculator, parsers::parse_into_expression},
         input_parsing::erasable_cluster::ErasableCluster,
     };
 
     #[test]
     fn expression_to_inexact_works() {
-        let cluster = ErasableCluster::build("t(45d) + S(0.5)^4 - 2a(-3)(8)^(2-1 +1)").unwrap();
+        let cluster = ErasableCluster::build("t(45d) + 4S(0.5) - 2a(-3)(8)^(2-1 +1)").unwrap();
         let mut calc = Calculator::build(&cluster).unwrap();
 
         let result = calc.next_inexact_output_mode().unwrap();
         // got that from the internet
-        let expected = -382.924838664;
+        let expected = -380.905604898;
 
         assert!((expected - result.value).abs() < 1e-4);
-        // radians cuz sine returns sine
-        assert_eq!(result.unit, Some(AngleUnit::Radians));
+        // radians cuz arcsine returns radians (and the numbers added to it are
+        // taken to be in radians)
+        assert_eq!(result.dimensions, Dimensions::ANGLE);
+        assert_eq!(result.angle_unit, AngleUnit::Radians);
 
         // this time in degrees
 
         let result = calc.next_inexact_output_mode().unwrap();
         // got that from the internet
-        let expected = -21939.9771;
+        let expected = -21824.2836;
 
         assert!((expected - result.value).abs() < 1e-4);
-        // radians cuz sine returns sine
-        assert_eq!(result.unit, Some(AngleUnit::Degrees));
+        assert_eq!(result.dimensions, Dimensions::ANGLE);
+        assert_eq!(result.angle_unit, AngleUnit::Degrees);
+    }
+
+    fn inexact(input: &str, angle_unit: AngleUnit) -> Result<String, String> {
+        let mut environment = Environment::new();
+        environment.set_angle_unit(angle_unit);
+
+        let cluster = ErasableCluster::build(input).unwrap();
+        let expression = parse_into_expression(cluster.iter()).unwrap();
+
+        expression_to_inexact(&expression, &environment)
+            .map(|result| format!("{result:.4}"))
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn angles_follow_the_unit_rules() {
+        let radians = AngleUnit::Radians;
+        let degrees = AngleUnit::Degrees;
+
+        // angle × number is an angle, in its own unit
+        assert_eq!(inexact("2(30d)", radians).unwrap(), "60.0000 deg");
+        assert_eq!(inexact("(30d)/2", radians).unwrap(), "15.0000 deg");
+        // angle + angle is in the unit of the one on the left
+        assert_eq!(inexact("90d + (p/2)r", radians).unwrap(), "180.0000 deg");
+        // and numbers added to angles are in the session's unit
+        assert_eq!(inexact("30d + 15", degrees).unwrap(), "45.0000 deg");
+        assert_eq!(inexact("1 + 30d", radians).unwrap(), "1.5236 rad");
+        // angle / angle is a number
+        assert_eq!(inexact("(90d)/((p/4)r)", radians).unwrap(), "2.0000");
+
+        assert_eq!(
+            inexact("30d(2r)", radians).unwrap_err(),
+            "error: angles can't be multiplied together"
+        );
+        assert_eq!(
+            inexact("1/(30d)", radians).unwrap_err(),
+            "error: a number can't be divided by an angle"
+        );
+        assert!(inexact("(30d)^2", radians).is_err());
+        assert!(inexact("S(30d)", radians).is_err());
+    }
+
+    #[test]
+    fn numbers_are_taken_to_be_in_the_session_unit() {
+        assert_eq!(inexact("s(30)", AngleUnit::Degrees).unwrap(), "0.5000");
+        assert_eq!(inexact("c(200)", AngleUnit::Gradians).unwrap(), "-1.0000");
+        assert_eq!(inexact("s(30)", AngleUnit::Radians).unwrap(), "-0.9880");
+        // unless they have a unit of their own
+        assert_eq!(inexact("s((p/6)r)", AngleUnit::Degrees).unwrap(), "0.5000");
+    }
+
+    #[test]
+    fn angles_can_be_in_any_unit() {
+        let radians = AngleUnit::Radians;
+
+        assert_eq!(inexact("100G + 0.25O", radians).unwrap(), "200.0000 grad");
+        assert_eq!(inexact("c(0.5O)", radians).unwrap(), "-1.0000");
+        assert_eq!(inexact("12D30'15\"", radians).unwrap(), "12.5042 deg");
+        assert_eq!(inexact("-(30')", radians).unwrap(), "-0.5000 deg");
+
+        let shown = |input: &str| {
+            let cluster = ErasableCluster::build(input).unwrap();
+            let expression = parse_into_expression(cluster.iter()).unwrap();
+            expression_to_inexact(&expression, &Environment::new())
+                .unwrap()
+                .into_degrees_minutes_seconds()
+        };
+
+        assert_eq!(shown("12D30'15\"").to_string(), "12°30'15\"");
+        assert_eq!(shown("(p/7)r").to_string(), "25°42'51.43\"");
+        assert_eq!(format!("{:.0}", shown("(p/7)r")), "25°42'51\"");
+        // the rounding carries over
+        assert_eq!(shown("59'59.999\"").to_string(), "1°0'0\"");
+        assert_eq!(shown("-0.5O").to_string(), "-180°0'0\"");
+        // numbers have no degrees to show
+        assert_eq!(shown("2.5").to_string(), "2.5");
+    }
+
+    #[test]
+    fn quantities_are_checked_and_converted() {
+        let radians = AngleUnit::Radians;
+
+        assert_eq!(inexact("2 km + 30 m", radians).unwrap(), "2030.0000 m");
+        assert_eq!(
+            inexact("9.8 m/s^2 * 2 kg", radians).unwrap(),
+            "19.6000 m kg/s^2"
+        );
+        assert_eq!(inexact("NthRoot(2, 9 m^2)", radians).unwrap(), "3.0000 m");
+        assert_eq!(inexact("60 Hz * 2 min", radians).unwrap(), "7200.0000");
+        assert_eq!(inexact("1 rad/s", radians).unwrap(), "1.0000 rad/s");
+        // angles are measured like any other unit
+        assert_eq!(inexact("30deg/s * 2 s", radians).unwrap(), "60.0000 deg");
+        assert_eq!(
+            inexact("30deg/s + 1 rad/s", radians).unwrap(),
+            "87.2958 deg/s"
+        );
+        assert_eq!(
+            inexact("1 rad/s + 1 m/s", radians).unwrap_err(),
+            "error: can't add m/s to rad/s"
+        );
+
+        assert_eq!(
+            inexact("5 s + 3 m", radians).unwrap_err(),
+            "error: can't add m to s"
+        );
+        assert_eq!(
+            inexact("s(2 m)", radians).unwrap_err(),
+            "error: expected an angle but found m"
+        );
+        assert_eq!(
+            inexact("(2 m)^0.5", radians).unwrap_err(),
+            "error: m can only be raised to whole powers"
+        );
+    }
+
+    #[test]
+    fn temperature_readings_are_added_and_taken_away() {
+        let radians = AngleUnit::Radians;
+
+        assert_eq!(inexact("20 °C + 5 K", radians).unwrap(), "298.1500 K");
+        assert_eq!(inexact("30 °C - 20 °C", radians).unwrap(), "10.0000 K");
+        assert_eq!(inexact("-5 °C", radians).unwrap(), "268.1500 K");
+        assert_eq!(inexact("10 °C - 5 K - 5 °C", radians).unwrap(), "0.0000 K");
+        assert_eq!(
+            inexact("0 °C + 0 °C", radians).unwrap_err(),
+            "error: can't add two temperature readings (add a difference in K instead)"
+        );
+        assert!(inexact("(0 °C) + (32 °F)", radians).is_err());
+        // only the size of a degree counts here
+        assert_eq!(inexact("2 °C/s * 3 s", radians).unwrap(), "6.0000 K");
+    }
+
+    #[test]
+    fn large_and_negative_unit_exponents_are_checked() {
+        let radians = AngleUnit::Radians;
+
+        assert_eq!(inexact("(2 m)^(-128)", radians).unwrap(), "0.0000 1/m^128");
+        assert_eq!(inexact("(1 m)^(127)", radians).unwrap(), "1.0000 m^127");
+        assert_eq!(
+            inexact("(2 m)^(100) * (3 m)^(100)", radians).unwrap_err(),
+            "error: unit exponent too large"
+        );
+        assert_eq!(
+            inexact("(2 m)^(128)", radians).unwrap_err(),
+            "error: unit exponent too large"
+        );
+        assert_eq!(
+            inexact("(1 m)^(-128)/(1 m)", radians).unwrap_err(),
+            "error: unit exponent too large"
+        );
+        assert_eq!(
+            inexact("(2 m)^(1000000000000)", radians).unwrap_err(),
+            "error: unit exponent too large"
+        );
     }
 }
@@ -0,0 +1,255 @@
+// contains the evaluation of expressions as whole numbers of a word size, for
+// bitwise operators (eg: 6 and 3) and everything in programmer mode.
+// Everything wraps around to fit the word size (see WordSize), and division
+// rounds toward zero, eg: 7/2 is 3
+
+use crate::shared::{errors::CalculationError, sign::Sign};
+
+use super::{
+    calculation_precision::WideValuePrecision,
+    calculator::{
+        BitwiseOperation, Expression, MultipliedOrDivided, Term, TermFragment,
+        TermFragmentMagnitude, UnnamedConstant, WordSize,
+    },
+    environment::Environment,
+    inexact::fragment_to_inexact,
+};
+
+type IntegerResult = Result<WideValuePrecision, CalculationError>;
+
+// the session's word size, or the default one outside programmer mode
+fn word_size(environment: &Environment) -> WordSize {
+    environment.word_size().unwrap_or(WordSize::DEFAULT)
+}
+
+pub(crate) fn bitwise_to_integer(
+    operation: &BitwiseOperation,
+    environment: &Environment,
+) -> IntegerResult {
+    let word_size = word_size(environment);
+    let integer = |expression| expression_to_integer(expression, environment);
+
+    // shifting by the word size or more shifts every bit out
+    let shift = |expression| match integer(expression)? {
+        shift if shift < 0 => Err(CalculationError::new(
+            "can't shift by a negative amount".to_string(),
+        )),
+        shift => Ok(shift.min(WideValuePrecision::from(word_size.bits()))),
+    };
+
+    let value = match operation {
+        BitwiseOperation::And(lhs, rhs) => integer(lhs)? & integer(rhs)?,
+        BitwiseOperation::Or(lhs, rhs) => integer(lhs)? | integer(rhs)?,
+        BitwiseOperation::Xor(lhs, rhs) => integer(lhs)? ^ integer(rhs)?,
+        BitwiseOperation::ShiftLeft(lhs, rhs) => integer(lhs)? << shift(rhs)?,
+        // which keeps the sign of signed numbers
+        BitwiseOperation::ShiftRight(lhs, rhs) => integer(lhs)? >> shift(rhs)?,
+        BitwiseOperation::Not(expression) => !integer(expression)?,
+    };
+
+    Ok(word_size.wrap(value))
+}
+
+fn power(base: &Expression, exponent: &Expression, environment: &Environment) -> IntegerResult {
+    let base = expression_to_integer(base, environment)?;
+    let exponent = expression_to_integer(exponent, environment)?;
+
+    let Ok(exponent) = u32::try_from(exponent) else {
+        return Err(CalculationError::new(format!(
+            "whole numbers can only be raised to whole powers from 0 to {}, not {exponent}",
+            u32::MAX
+        )));
+    };
+
+    Ok(word_size(environment).wrap(base.wrapping_pow(exponent)))
+}
+
+fn fragment_to_integer(fragment: &TermFragment, environment: &Environment) -> IntegerResult {
+    use TermFragmentMagnitude::*;
+
+    let magnitude = match &fragment.fragment_magnitude {
+        _ if fragment.angle_unit.is_some() => None,
+        NonNamedConstant(
+            UnnamedConstant::Integer(value)
+            | UnnamedConstant::Hexadecimal(value)
+            | UnnamedConstant::Binary(value)
+            | UnnamedConstant::Octal(value),
+        ) => Some(WideValuePrecision::from(*value)),
+        NonNamedConstant(UnnamedConstant::Power { base, exponent }) => {
+            Some(power(base, exponent, environment)?)
+        }
+        Bracket(expression) => Some(expression_to_integer(expression, environment)?),
+        Bitwise(operation) => Some(bitwise_to_integer(operation, environment)?),
+        _ => None,
+    };
+
+    let value = match magnitude {
+        Some(magnitude) if fragment.sign == Sign::Negative => magnitude.wrapping_neg(),
+        Some(magnitude) => magnitude,
+        // anything else (eg: a variable) is worked out as usual, but has to
+        // come out whole
+        None => {
+            let inexact = fragment_to_inexact(fragment, environment)?;
+
+            if inexact.has_units() {
+                let place = match environment.word_size() {
+                    Some(_) => "in programmer mode",
+                    None => "with bitwise operators",
+                };
+
+                return Err(CalculationError::new(format!(
+                    "units can't be used {place}"
+                )));
+            }
+
+            inexact
+                .as_integer()
+                .ok_or_else(|| CalculationError::new(format!("{inexact} isn't a whole number")))?
+        }
+    };
+
+    Ok(word_size(environment).wrap(value))
+}
+
+fn term_to_integer(term: &Term, environment: &Environment) -> IntegerResult {
+    let word_size = word_size(environment);
+
+    // rounded toward zero
+    let divide = |lhs: WideValuePrecision, rhs| match rhs {
+        0 => Err(CalculationError::new("can't divide by zero".to_string())),
+        rhs => Ok(word_size.wrap(lhs.wrapping_div(rhs))),
+    };
+
+    let mut result: Option<WideValuePrecision> = None;
+
+    for fragment in &term.fragments {
+        let integer = fragment_to_integer(fragment, environment)?;
+
+        result = Some(match (result, fragment.multiplied_or_divided) {
+            (Some(product), MultipliedOrDivided::Divided) => divide(product, integer)?,
+            (Some(product), _) => word_size.wrap(product.wrapping_mul(integer)),
+            (None, MultipliedOrDivided::Divided) => divide(1, integer)?,
+            (None, _) => integer,
+        });
+    }
+
+    result.ok_or(CalculationError::new("unexpected empty term".to_string()))
+}
+
+pub(crate) fn expression_to_integer(
+    expression: &Expression,
+    environment: &Environment,
+) -> IntegerResult {
+    if expression.is_empty() {
+        return Err(CalculationError::new("empty expression".to_string()));
+    }
+
+    let mut sum: WideValuePrecision = 0;
+
+    for term in expression {
+        let term = term_to_integer(term, environment)?;
+        sum = word_size(environment).wrap(sum.wrapping_add(term));
+    }
+
+    Ok(sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        calculation::{
+            calculator::{Calculator, OutputMode, OutputSettings},
+            formatting::NumberFormat,
+        },
+        input_parsing::erasable_cluster::ErasableCluster,
+    };
+
+    use super::*;
+
+    // the result in the output mode, or the error
+    fn output(input: &str, word_size: Option<&str>, output_mode: OutputMode) -> String {
+        let mut environment = Environment::new();
+        environment.set_word_size(word_size.map(|word_size| word_size.parse().unwrap()));
+
+        let cluster = ErasableCluster::build(input).unwrap();
+        let mut calc = Calculator::build_with_environment(&cluster, &environment)
+            .unwrap()
+            .with_output_settings(OutputSettings {
+                output_mode,
+                number_format: NumberFormat::default(),
+            });
+
+        calc.next_output_mode().unwrap_or_else(|e| e.to_string())
+    }
+
+    #[test]
+    fn bitwise_operators_work() {
+        let result = |input| output(input, None, OutputMode::Decimal);
+
+        assert_eq!(result("6 and 3"), "2");
+        assert_eq!(result("6 or 3"), "7");
+        assert_eq!(result("6 xor 3"), "5");
+        assert_eq!(result("not 0"), "-1");
+        assert_eq!(result("1 << 4"), "16");
+        assert_eq!(result("-16 >> 2"), "-4");
+        // shifts bind tightest, then and, xor and or; arithmetic before all of them
+        assert_eq!(result("1 or 2 and 3 << 1"), "3");
+        assert_eq!(result("1 + 2 and 3"), "3");
+        assert_eq!(result("(1 or 2) * 2"), "6");
+        assert_eq!(result("1 << 64"), "0");
+        assert_eq!(result("1 << -1"), "error: can't shift by a negative amount");
+        assert_eq!(result("3.5 and 1"), "error: 3.5 isn't a whole number");
+        assert_eq!(
+            result("5 km and 1"),
+            "error: units can't be used with bitwise operators"
+        );
+    }
+
+    #[test]
+    fn programmer_mode_wraps_around() {
+        let result = |input, word_size| output(input, Some(word_size), OutputMode::Decimal);
+
+        assert_eq!(result("255 + 1", "u8"), "0");
+        assert_eq!(result("127 + 1", "i8"), "-128");
+        assert_eq!(result("0 - 1", "u16"), "65535");
+        assert_eq!(result("7/2", "i32"), "3");
+        assert_eq!(result("-7/2", "i32"), "-3");
+        assert_eq!(result("not 0", "u8"), "255");
+        assert_eq!(result("2^8", "u8"), "0");
+        assert_eq!(result("1/0", "i64"), "error: can't divide by zero");
+        assert_eq!(
+            result("5 km", "u8"),
+            "error: units can't be used in programmer mode"
+        );
+        // not rounded through a decimal
+        assert_eq!(result("0 - 1", "u64"), "18446744073709551615");
+
+        assert_eq!(output("-1", Some("i8"), OutputMode::Hexadecimal), "0xFF");
+        assert_eq!(
+            output("-1", Some("i16"), OutputMode::Binary),
+            "0b1111111111111111"
+        );
+        assert_eq!(output("-1", None, OutputMode::Hexadecimal), "-0x1");
+    }
+
+    #[test]
+    fn word_sizes_are_switched_through() {
+        let mut word_size = None;
+        let mut names = vec![];
+
+        loop {
+            word_size = WordSize::next(word_size);
+
+            match word_size {
+                Some(word_size) => names.push(word_size.to_string()),
+                None => break,
+            }
+        }
+
+        assert_eq!(
+            names,
+            ["i8", "u8", "i16", "u16", "i32", "u32", "i64", "u64"]
+        );
+        assert!("u128".parse::<WordSize>().is_err());
+    }
+}
@@ -4,10 +4,16 @@ use self::inexact::Inexact;
 
 pub(crate) mod calculation_precision;
 pub mod calculator;
-pub(self) mod helpers;
-pub(self) mod inexact;
-pub(self) mod parsers;
-pub(self) mod rational_number;
-pub(self) mod wrapped_iter;
+pub mod environment;
+mod exact;
+pub mod formatting;
+mod helpers;
+mod inexact;
+mod integer;
+mod parsers;
+mod rational_number;
+mod surd;
+pub mod units;
+mod wrapped_iter;
 
-pub(self) type CalculationResult = Result<Inexact, CalculationError>;
+type CalculationResult = Result<Inexact, CalculationError>;
@@ -1,4 +1,4 @@
-use std::{iter::Peekable, slice::Iter};
+use std::{iter::Peekable, rc::Rc, slice::Iter};
 
 use num_traits::ToPrimitive;
 
@@ -10,9 +10,11 @@ use crate::{
 use super::{
     calculation_precision::UnsignedValuePrecision,
     calculator::{
-        AngleUnit, Expression, Function, MultipliedOrDivided, NamedConstant, Term, TermFragment,
-        TermFragmentMagnitude, UnnamedConstant,
+        AngleUnit, BitwiseOperation, Expression, Function, MultipliedOrDivided, NamedConstant,
+        Statement, Term, TermFragment, TermFragmentMagnitude, UnnamedConstant,
     },
+    environment::{UserFunction, Variable},
+    units::{prefix_factor, Unit},
     wrapped_iter::WrappedIter,
 };
 
@@ -80,11 +82,8 @@ fn parse_into_int_or_decimal(
     let mut before_decimal_point = String::new();
     let mut after_decimal_point = String::new();
 
-    loop {
-        let erasable = match iterator.peek() {
-            Some(e) => *e,
-            None => break,
-        };
+    while let Some(erasable) = iterator.peek() {
+        let erasable = *erasable;
         let erasable_type: ErasableType = erasable.into();
 
         match erasable_type {
@@ -100,6 +99,10 @@ fn parse_into_int_or_decimal(
 
                 iterator.next();
             }
+            // eg: the x of 0x1F
+            ErasableType::BasePrefix if before_decimal_point == "0" && !was_decimal_point_met => {
+                return parse_integer_in_base(iterator);
+            }
             ErasableType::DecimalPoint => {
                 if was_decimal_point_met {
                     return ParsingResult::Err(ParsingError::ExcessiveDecimalPoints);
@@ -129,6 +132,73 @@ fn parse_into_int_or_decimal(
     }
 }
 
+// the rest of an integer in another base, from the x, b or o after its 0
+fn parse_integer_in_base(iterator: &mut Peekable<WrappedIter>) -> ParsingResult<UnnamedConstant> {
+    let prefix = some_from_option_or_will_error!(iterator.next());
+
+    let (radix, base) = match prefix {
+        Erasable::HexadecimalPrefix => (16, "hexadecimal"),
+        Erasable::BinaryPrefix => (2, "binary"),
+        Erasable::OctalPrefix => (8, "octal"),
+        prefix => return ParsingResult::Err(ParsingError::Unexpected(prefix.to_string())),
+    };
+
+    let mut digits = String::new();
+
+    while let Some(erasable) = iterator.peek() {
+        if !matches!(
+            ErasableType::from(*erasable),
+            ErasableType::Digit | ErasableType::HexDigit
+        ) {
+            break;
+        }
+
+        digits.push_str(&erasable.to_string());
+        iterator.next();
+    }
+
+    if let Some(digit) = digits.chars().find(|digit| !digit.is_digit(radix)) {
+        return ParsingResult::Err(ParsingError::ExpectedButFound {
+            expected: format!("a {base} digit"),
+            found: digit.to_string(),
+        });
+    }
+
+    if digits.is_empty() {
+        return ParsingResult::Err(ParsingError::EndOfInput);
+    }
+
+    let value = some_from_result!(UnsignedValuePrecision::from_str_radix(&digits, radix));
+
+    ParsingResult::Some(match prefix {
+        Erasable::HexadecimalPrefix => UnnamedConstant::Hexadecimal(value),
+        Erasable::BinaryPrefix => UnnamedConstant::Binary(value),
+        _ => UnnamedConstant::Octal(value),
+    })
+}
+
+// the digits straight after `ans`; eg: ans3 refers to the third result
+fn parse_history_index(iterator: &mut Peekable<WrappedIter>) -> ParsingResult<Option<usize>> {
+    let mut digits = String::new();
+
+    while let Some(erasable) = iterator.peek() {
+        if ErasableType::from(*erasable) != ErasableType::Digit {
+            break;
+        }
+
+        let digit = <Erasable as ToPrimitive>::to_u8(*erasable);
+        digits.push(some_from_option_or_will_error!(digit) as char);
+
+        iterator.next();
+    }
+
+    if digits.is_empty() {
+        ParsingResult::Some(None)
+    } else {
+        ParsingResult::Some(Some(some_from_result!(digits.parse::<usize>())))
+    }
+}
+
 fn parse_term_fragment_operators(
     iterator: &mut Peekable<WrappedIter>,
 ) -> (Option<Sign>, Option<MultipliedOrDivided>) {
@@ -139,7 +209,8 @@ fn parse_term_fragment_operators(
                 iterator.next();
                 MultipliedOrDivided::Multiplied
             }
-            Erasable::DivisionSign => {
+            // eg: 1_2 is the fraction 1/2
+            Erasable::DivisionSign | Erasable::FractionDivider => {
                 iterator.next();
                 MultipliedOrDivided::Divided
             }
@@ -204,7 +275,7 @@ fn parse_term_fragment_brackets(iterator: &mut Peekable<WrappedIter>) -> Parsing
     loop {
         let erasable = iterator.peek();
 
-        if let None = erasable {
+        if erasable.is_none() {
             return ParsingResult::Err(ParsingError::MismatchedBrackets);
         }
 
@@ -239,6 +310,8 @@ fn parse_term_fragment_brackets(iterator: &mut Peekable<WrappedIter>) -> Parsing
     }
 }
 
+// eg: (2, 8) in NthRoot(2, 8). The arguments are separated by commas whatever
+// the locale, as a ; typed with a decimal comma is read as one
 fn parse_function_argument_list(
     iterator: &mut Peekable<WrappedIter>,
 ) -> ParsingResult<Vec<Expression>> {
@@ -258,7 +331,7 @@ fn parse_function_argument_list(
         'inner: loop {
             let erasable = iterator.peek();
 
-            if erasable == None {
+            if erasable.is_none() {
                 return ParsingResult::Err(ParsingError::MismatchedBrackets);
             }
 
@@ -374,28 +447,46 @@ fn parse_term_fragment(
                 return ParsingResult::Err(ParsingError::Unexpected(erasable.to_string()))
             }
             ErasableType::NamedConstant => {
-                let result = TermFragment {
+                let constant = match erasable {
+                    Erasable::Pi => NamedConstant::Pi,
+                    Erasable::E => NamedConstant::E,
+                    Erasable::Answer => NamedConstant::Answer(None),
+                    _ => return ParsingResult::Err(ParsingError::Unexpected(erasable.to_string())),
+                };
+
+                iterator.next();
+
+                let constant = match constant {
+                    NamedConstant::Answer(_) => NamedConstant::Answer(
+                        some_from_parsing_result_or_return!(parse_history_index(iterator)),
+                    ),
+                    constant => constant,
+                };
+
+                TermFragment {
                     sign: sign.unwrap_or_default(),
                     fragment_magnitude: TermFragmentMagnitude::NamedConstant {
                         coefficient: integer_as_expression(1),
-                        constant: match erasable {
-                            Erasable::Pi => NamedConstant::Pi,
-                            Erasable::E => NamedConstant::E,
-                            _ => {
-                                return ParsingResult::Err(ParsingError::Unexpected(
-                                    erasable.to_string(),
-                                ))
-                            }
-                        },
+                        constant,
                     },
                     angle_unit: None,
                     multiplied_or_divided: multiplied_or_divided.unwrap_or_default(),
-                };
-
-                iterator.next();
-                result
+                }
             }
-            ErasableType::AngleUnit => {
+            // a unit on its own is one of it, eg: the h of km/h
+            ErasableType::AngleUnit | ErasableType::Unit => TermFragment {
+                sign: sign.unwrap_or_default(),
+                fragment_magnitude: TermFragmentMagnitude::Unit(
+                    some_from_parsing_result_or_return!(parse_unit(iterator)),
+                ),
+                multiplied_or_divided: multiplied_or_divided.unwrap_or_default(),
+                angle_unit: None,
+            },
+            // only valid between what's converted and the units it's converted into,
+            // and in an integer in another base (after its 0)
+            ErasableType::ConversionOperator
+            | ErasableType::BasePrefix
+            | ErasableType::HexDigit => {
                 return ParsingResult::Err(ParsingError::Unexpected(erasable.to_string()))
             }
             ErasableType::Digit | ErasableType::DecimalPoint => TermFragment {
@@ -406,7 +497,7 @@ fn parse_term_fragment(
                 sign: sign.unwrap_or_default(),
                 angle_unit: None,
             },
-            ErasableType::ArithmeticOperator => {
+            ErasableType::ArithmeticOperator | ErasableType::FractionDivider => {
                 let (sign, multiplied_or_divided) = parse_term_fragment_operators(iterator);
                 some_from_parsing_result_or_return!(parse_term_fragment(
                     iterator,
@@ -414,7 +505,6 @@ fn parse_term_fragment(
                     multiplied_or_divided
                 ))
             }
-            ErasableType::FractionDivider => todo!(),
             ErasableType::ExponentPlaceholder => {
                 return ParsingResult::Err(ParsingError::Unexpected(erasable.to_string()))
             }
@@ -432,6 +522,59 @@ fn parse_term_fragment(
                 multiplied_or_divided: multiplied_or_divided.unwrap_or_default(),
                 angle_unit: None,
             },
+            ErasableType::Variable => {
+                let variable = some_from_result!(Variable::try_from(*erasable));
+                iterator.next();
+
+                // eg: f(3), which is only known to be a call (rather than
+                // implicit multiplication) once it's evaluated
+                let is_call = iterator
+                    .peek()
+                    .is_some_and(|next| ErasableType::from(*next) == ErasableType::OpeningBracket);
+
+                let fragment_magnitude = if is_call {
+                    TermFragmentMagnitude::Function(Function::User {
+                        name: variable,
+                        arguments: some_from_parsing_result_or_return!(
+                            parse_function_argument_list(iterator)
+                        ),
+                    })
+                } else {
+                    TermFragmentMagnitude::Variable(variable)
+                };
+
+                TermFragment {
+                    sign: sign.unwrap_or_default(),
+                    fragment_magnitude,
+                    multiplied_or_divided: multiplied_or_divided.unwrap_or_default(),
+                    angle_unit: None,
+                }
+            }
+            // eg: not 6
+            ErasableType::BitwiseOperator if *erasable == &Erasable::BitwiseNot => {
+                iterator.next();
+                let operand = parse_term_fragment(iterator, None, None);
+
+                TermFragment {
+                    sign: sign.unwrap_or_default(),
+                    fragment_magnitude: TermFragmentMagnitude::Bitwise(BitwiseOperation::Not(
+                        vec![Term {
+                            fragments: vec![some_from_parsing_result_or_return!(operand)],
+                        }],
+                    )),
+                    multiplied_or_divided: multiplied_or_divided.unwrap_or_default(),
+                    angle_unit: None,
+                }
+            }
+            // the others are only valid between two expressions, so they're
+            // split at first
+            ErasableType::BitwiseOperator => {
+                return ParsingResult::Err(ParsingError::Unexpected(erasable.to_string()))
+            }
+            // only valid straight after the variable being assigned to
+            ErasableType::AssignmentOperator => {
+                return ParsingResult::Err(ParsingError::Unexpected(erasable.to_string()))
+            }
         };
 
         for _ in 1..=2 {
@@ -446,7 +589,12 @@ fn parse_term_fragment(
                             fragment_magnitude: TermFragmentMagnitude::NonNamedConstant(
                                 UnnamedConstant::Power {
                                     base: vec![Term {
-                                        fragments: vec![TermFragment { ..base }],
+                                        // the power is what's divided (eg: in
+                                        // 8/2^2), not the base inside it
+                                        fragments: vec![TermFragment {
+                                            multiplied_or_divided: MultipliedOrDivided::default(),
+                                            ..base
+                                        }],
                                     }],
                                     exponent: vec![Term {
                                         fragments: vec![exponent],
@@ -466,6 +614,19 @@ fn parse_term_fragment(
                         iterator.next();
                         base.angle_unit = Some(AngleUnit::Radians);
                     }
+                    Erasable::Gradians => {
+                        iterator.next();
+                        base.angle_unit = Some(AngleUnit::Gradians);
+                    }
+                    Erasable::Turns => {
+                        iterator.next();
+                        base.angle_unit = Some(AngleUnit::Turns);
+                    }
+                    Erasable::DegreeSign | Erasable::ArcMinutes | Erasable::ArcSeconds => {
+                        base = some_from_parsing_result_or_return!(parse_degrees_minutes_seconds(
+                            iterator, base
+                        ));
+                    }
                     _ => break,
                 }
             }
@@ -477,35 +638,159 @@ fn parse_term_fragment(
     }
 }
 
-fn peek_next_term_fragment(iterator: &mut Peekable<WrappedIter>) -> ParsingResult<TermFragment> {
-    parse_term_fragment(&mut (iterator.clone()), None, None)
+// a unit, with its prefix if it has one; eg: km
+fn parse_unit(iterator: &mut Peekable<WrappedIter>) -> ParsingResult<Unit> {
+    let first = some_from_option_or_will_error!(iterator.next());
+
+    let unit = match prefix_factor(first) {
+        Some(_) => Unit::build(
+            Some(first),
+            some_from_option_or_will_error!(iterator.next()),
+        ),
+        None => Unit::build(None, first),
+    };
+
+    match unit {
+        Some(unit) => ParsingResult::Some(unit),
+        None => ParsingResult::Err(ParsingError::Unexpected(first.to_string())),
+    }
+}
+
+// the place of the degree, minute and second signs in an angle like 12°30'15",
+// and how many of each make a degree
+fn degrees_minutes_seconds_part(erasable: &Erasable) -> Option<(usize, UnsignedValuePrecision)> {
+    match erasable {
+        Erasable::DegreeSign => Some((0, 1)),
+        Erasable::ArcMinutes => Some((1, 60)),
+        Erasable::ArcSeconds => Some((2, 3600)),
+        _ => None,
+    }
+}
+
+// eg: 12°30'15", which is 12 + 30/60 + 15/3600 degrees. `first` is what comes
+// before the first sign; any other parts must be numbers.
+fn parse_degrees_minutes_seconds(
+    iterator: &mut Peekable<WrappedIter>,
+    first: TermFragment,
+) -> ParsingResult<TermFragment> {
+    let mut terms = vec![];
+    let mut magnitude = first.fragment_magnitude;
+    let mut previous_part = None;
+
+    loop {
+        let sign = some_from_option_or_will_error!(iterator.next());
+
+        let Some((part, per_degree)) = degrees_minutes_seconds_part(sign) else {
+            return ParsingResult::Err(ParsingError::Unexpected(sign.to_string()));
+        };
+
+        // eg: 30'12° is out of order
+        if previous_part.is_some_and(|previous_part| part <= previous_part) {
+            return ParsingResult::Err(ParsingError::Unexpected(sign.to_string()));
+        }
+
+        previous_part = Some(part);
+
+        let mut fragments = vec![TermFragment {
+            sign: Sign::Positive,
+            fragment_magnitude: magnitude,
+            multiplied_or_divided: MultipliedOrDivided::default(),
+            angle_unit: None,
+        }];
+
+        if per_degree > 1 {
+            fragments.push(TermFragment {
+                sign: Sign::Positive,
+                fragment_magnitude: TermFragmentMagnitude::NonNamedConstant(
+                    UnnamedConstant::Integer(per_degree),
+                ),
+                multiplied_or_divided: MultipliedOrDivided::Divided,
+                angle_unit: None,
+            });
+        }
+
+        terms.push(Term { fragments });
+
+        // the angle goes on if a number and another sign come next
+        let mut lookahead = iterator.clone();
+
+        let is_number = lookahead.peek().is_some_and(|next| {
+            matches!(
+                ErasableType::from(*next),
+                ErasableType::Digit | ErasableType::DecimalPoint
+            )
+        });
+
+        if !is_number {
+            break;
+        }
+
+        let ParsingResult::Some(number) = parse_into_int_or_decimal(&mut lookahead) else {
+            break;
+        };
+
+        if lookahead
+            .peek()
+            .is_none_or(|next| degrees_minutes_seconds_part(next).is_none())
+        {
+            break;
+        }
+
+        *iterator = lookahead;
+        magnitude = TermFragmentMagnitude::NonNamedConstant(number);
+    }
+
+    ParsingResult::Some(TermFragment {
+        sign: first.sign,
+        fragment_magnitude: TermFragmentMagnitude::Bracket(terms),
+        multiplied_or_divided: first.multiplied_or_divided,
+        angle_unit: Some(AngleUnit::Degrees),
+    })
+}
+
+// whether the next term fragment is multiplied, divided or starts a new term,
+// going by the operators before it; parsing the whole fragment to find out
+// would parse nested brackets over and over (taking exponential time)
+fn peek_next_multiplied_or_divided(
+    iterator: &Peekable<WrappedIter>,
+) -> Option<MultipliedOrDivided> {
+    let mut iterator = iterator.clone();
+    let mut multiplied_or_divided = None;
+
+    loop {
+        match ErasableType::from(*iterator.peek()?) {
+            ErasableType::Formatting => {
+                iterator.next();
+            }
+            ErasableType::ArithmeticOperator | ErasableType::FractionDivider => {
+                multiplied_or_divided = parse_term_fragment_operators(&mut iterator).1;
+            }
+            _ => return Some(multiplied_or_divided.unwrap_or_default()),
+        }
+    }
 }
 
 fn parse_term(iterator: &mut Peekable<WrappedIter>) -> ParsingResult<Term> {
     let mut term = Term { fragments: vec![] };
 
-    let fragment = peek_next_term_fragment(iterator);
-    let fragment = some_from_parsing_result_or_return!(fragment);
+    let Some(multiplied_or_divided) = peek_next_multiplied_or_divided(iterator) else {
+        return ParsingResult::None;
+    };
 
     // signifies the start of a new term
-    if let MultipliedOrDivided::Neither = fragment.multiplied_or_divided {
+    if let MultipliedOrDivided::Neither = multiplied_or_divided {
         let fragment = parse_term_fragment(iterator, None, None);
         let fragment = some_from_parsing_result_or_return!(fragment);
         term.fragments.push(fragment);
 
         loop {
-            match peek_next_term_fragment(iterator) {
-                ParsingResult::Err(e) => return ParsingResult::Err(e),
-                ParsingResult::Some(fragment) => {
-                    if let MultipliedOrDivided::Neither = fragment.multiplied_or_divided {
-                        break;
-                    }
-
+            match peek_next_multiplied_or_divided(iterator) {
+                Some(MultipliedOrDivided::Neither) | None => break,
+                Some(_) => {
                     term.fragments.push(some_from_parsing_result_or_will_error!(
                         parse_term_fragment(iterator, None, None)
                     ));
                 }
-                ParsingResult::None => break,
             }
         }
 
@@ -517,9 +802,79 @@ fn parse_term(iterator: &mut Peekable<WrappedIter>) -> ParsingResult<Term> {
     }
 }
 
+// makes a bitwise operation from what's either side of its operator
+type BinaryBitwiseOperation = fn(Expression, Expression) -> BitwiseOperation;
+
+// how tightly a binary bitwise operator binds (the lowest is worked out last),
+// and the operation it makes
+fn binary_bitwise_operator(erasable: &Erasable) -> Option<(u8, BinaryBitwiseOperation)> {
+    match erasable {
+        Erasable::BitwiseOr => Some((0, BitwiseOperation::Or)),
+        Erasable::BitwiseXor => Some((1, BitwiseOperation::Xor)),
+        Erasable::BitwiseAnd => Some((2, BitwiseOperation::And)),
+        Erasable::ShiftLeft => Some((3, BitwiseOperation::ShiftLeft)),
+        Erasable::ShiftRight => Some((3, BitwiseOperation::ShiftRight)),
+        _ => None,
+    }
+}
+
+// the binary bitwise operator (outside brackets) that's worked out last, which
+// is the rightmost of the loosest ones; eg: the or of 1 or 2 and 3 or 4
+fn last_bitwise_operator(erasables: &[Erasable]) -> Option<usize> {
+    let mut bracket_depth = 0;
+    let mut last: Option<(usize, u8)> = None;
+
+    for (index, erasable) in erasables.iter().enumerate() {
+        match ErasableType::from(erasable) {
+            ErasableType::OpeningBracket => bracket_depth += 1,
+            ErasableType::ClosingBracket => bracket_depth -= 1,
+            _ if bracket_depth == 0 => {
+                let Some((precedence, _)) = binary_bitwise_operator(erasable) else {
+                    continue;
+                };
+
+                if last.is_none_or(|(_, loosest)| precedence <= loosest) {
+                    last = Some((index, precedence));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    last.map(|(index, _)| index)
+}
+
 pub(crate) fn parse_into_expression(
     iterator: Iter<'_, Erasable>,
 ) -> Result<Expression, ParsingError> {
+    let erasables = iterator.as_slice();
+
+    // binary bitwise operators are worked out after everything around them,
+    // eg: 1 + 2 and 3 is (1 + 2) and 3
+    if let Some(index) = last_bitwise_operator(erasables) {
+        let (lhs, rhs) = (&erasables[..index], &erasables[index + 1..]);
+
+        let lhs = parse_into_expression(lhs.iter())?;
+        let rhs = parse_into_expression(rhs.iter())?;
+
+        if lhs.is_empty() || rhs.is_empty() {
+            return Err(ParsingError::EndOfInput);
+        }
+
+        let Some((_, operation)) = binary_bitwise_operator(&erasables[index]) else {
+            return Err(ParsingError::Unexpected(erasables[index].to_string()));
+        };
+
+        return Ok(vec![Term {
+            fragments: vec![TermFragment {
+                fragment_magnitude: TermFragmentMagnitude::Bitwise(operation(lhs, rhs)),
+                multiplied_or_divided: MultipliedOrDivided::default(),
+                sign: Sign::default(),
+                angle_unit: None,
+            }],
+        }]);
+    }
+
     let mut expression = vec![];
 
     let mut iterator = WrappedIter::from(iterator).peekable();
@@ -537,6 +892,137 @@ pub(crate) fn parse_into_expression(
     Ok(expression)
 }
 
+// the parameters of a function definition; eg: (x, y) in f(x, y) = x + y
+fn parse_parameter_list(erasables: &[&Erasable]) -> Result<Vec<Variable>, ParsingError> {
+    let Some((opening_bracket, rest)) = erasables.split_first() else {
+        return Err(ParsingError::EndOfInput);
+    };
+
+    if ErasableType::from(*opening_bracket) != ErasableType::OpeningBracket {
+        return Err(ParsingError::ExpectedButFound {
+            expected: Erasable::EqualsSign.to_string(),
+            found: opening_bracket.to_string(),
+        });
+    }
+
+    let Some((closing_bracket, inside)) = rest.split_last() else {
+        return Err(ParsingError::MismatchedBrackets);
+    };
+
+    if ErasableType::from(*closing_bracket) != ErasableType::ClosingBracket {
+        return Err(ParsingError::MismatchedBrackets);
+    }
+
+    let mut parameters: Vec<Variable> = vec![];
+
+    for parameter in inside.split(|erasable| ErasableType::from(*erasable) == ErasableType::Comma) {
+        let parameter = match parameter {
+            [parameter] => Variable::try_from(*parameter)?,
+            _ => {
+                return Err(ParsingError::ExpectedButFound {
+                    expected: "parameter".to_string(),
+                    found: parameter
+                        .iter()
+                        .map(|erasable| erasable.to_string())
+                        .collect(),
+                })
+            }
+        };
+
+        if parameters.contains(&parameter) {
+            return Err(ParsingError::Custom(format!(
+                "{parameter} is used for more than one parameter"
+            )));
+        }
+
+        parameters.push(parameter);
+    }
+
+    Ok(parameters)
+}
+
+// `<expression> -> <units>`, eg: 5 km/h -> m/s
+fn parse_conversion(erasables: &[Erasable], arrow: usize) -> Result<Statement, ParsingError> {
+    let (expression, units) = (&erasables[..arrow], &erasables[arrow + 1..]);
+
+    let expression = parse_into_expression(expression.iter())?;
+    let units_expression = parse_into_expression(units.iter())?;
+
+    if expression.is_empty() || units_expression.is_empty() {
+        return Err(ParsingError::EndOfInput);
+    }
+
+    Ok(Statement::Conversion {
+        expression,
+        units: units_expression,
+        units_name: units
+            .iter()
+            .filter(|erasable| ErasableType::from(*erasable) != ErasableType::Formatting)
+            .map(|erasable| erasable.to_string())
+            .collect(),
+    })
+}
+
+// `<variable> = <expression>`, `<function>(<parameters>) = <expression>`,
+// `<expression> -> <units>` or just `<expression>`
+pub(crate) fn parse_into_statement(
+    iterator: Iter<'_, Erasable>,
+) -> Result<Statement, ParsingError> {
+    let erasables = iterator.as_slice();
+
+    if let Some(arrow) = erasables
+        .iter()
+        .position(|erasable| ErasableType::from(erasable) == ErasableType::ConversionOperator)
+    {
+        return parse_conversion(erasables, arrow);
+    }
+
+    let equals_sign = erasables
+        .iter()
+        .position(|erasable| ErasableType::from(erasable) == ErasableType::AssignmentOperator);
+
+    let Some(equals_sign) = equals_sign else {
+        return Ok(Statement::Expression(parse_into_expression(iterator)?));
+    };
+
+    let (left, right) = (&erasables[..equals_sign], &erasables[equals_sign + 1..]);
+
+    let left: Vec<&Erasable> = left
+        .iter()
+        .filter(|erasable| ErasableType::from(*erasable) != ErasableType::Formatting)
+        .collect();
+
+    let Some((name, parameters)) = left.split_first() else {
+        return Err(ParsingError::ExpectedButFound {
+            expected: "variable".to_string(),
+            found: Erasable::EqualsSign.to_string(),
+        });
+    };
+
+    let name = Variable::try_from(*name)?;
+
+    let expression = parse_into_expression(right.iter())?;
+
+    if expression.is_empty() {
+        return Err(ParsingError::EndOfInput);
+    }
+
+    if parameters.is_empty() {
+        return Ok(Statement::Assignment {
+            variable: name,
+            expression,
+        });
+    }
+
+    Ok(Statement::FunctionDefinition {
+        name,
+        function: Rc::new(UserFunction {
+            parameters: parse_parameter_list(parameters)?,
+            body: expression,
+        }),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use crate::input_parsing::erasable_cluster::ErasableCluster;
@@ -547,6 +1033,53 @@ mod tests {
     fn parsing_works() {
         let cluster = ErasableCluster::build("10204.12p").unwrap();
 
-        let expr = parse_into_expression(cluster.iter());
+        let _expr = parse_into_expression(cluster.iter());
+    }
+
+    #[test]
+    fn degrees_minutes_and_seconds_are_parsed() {
+        let parses = |input: &str| {
+            let cluster = ErasableCluster::build(input).unwrap();
+            parse_into_expression(cluster.iter()).is_ok()
+        };
+
+        assert!(parses("12D30'15\""));
+        assert!(parses("12D15\""));
+        assert!(parses("2(30')"));
+        assert!(!parses("12'30D"));
+        assert!(!parses("12D30'15'"));
+    }
+
+    #[test]
+    fn assignments_are_parsed() {
+        let cluster = ErasableCluster::build("x = 2y").unwrap();
+        let statement = parse_into_statement(cluster.iter()).unwrap();
+
+        assert!(matches!(statement, Statement::Assignment { .. }));
+
+        let cluster = ErasableCluster::build("f(x, y) = xy + 1").unwrap();
+        let statement = parse_into_statement(cluster.iter()).unwrap();
+
+        match statement {
+            Statement::FunctionDefinition { function, .. } => {
+                assert_eq!(function.parameters.len(), 2)
+            }
+            _ => panic!("expected a function definition"),
+        }
+
+        for invalid in [
+            "= 2",
+            "2 = x",
+            "x y = 2",
+            "x = ",
+            "x = y = 2",
+            "f() = 2",
+            "f(x, x) = x",
+            "f(2) = 3",
+            "f(x = x",
+        ] {
+            let cluster = ErasableCluster::build(invalid).unwrap();
+            assert!(parse_into_statement(cluster.iter()).is_err(), "{invalid}");
+        }
     }
 }
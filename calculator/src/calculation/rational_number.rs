@@ -1,12 +1,12 @@
 use crate::shared::sign::Sign;
-use std::ops::Add;
+use std::ops::{Add, Neg};
 
 use super::{
-    calculation_precision::{SignedValuePrecision, UnsignedValuePrecision},
+    calculation_precision::{FloatingPointPrecison, SignedValuePrecision, UnsignedValuePrecision},
     helpers::hcf,
 };
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub struct RationalNumber {
     numerator: UnsignedValuePrecision,
     denominator: UnsignedValuePrecision,
@@ -20,63 +20,148 @@ impl RationalNumber {
         let is_numerator_negative = numerator < 0;
         let is_denominator_negative = denominator < 0;
 
-        let numerator = numerator.abs() as UnsignedValuePrecision;
-        let denominator = denominator.abs() as UnsignedValuePrecision;
+        Self::from_parts(
+            numerator.unsigned_abs(),
+            denominator.unsigned_abs(),
+            Sign::from(is_denominator_negative == is_numerator_negative),
+        )
+    }
+
+    /// Builds a rational number in its lowest terms. Zero is always positive.
+    fn from_parts(
+        numerator: UnsignedValuePrecision,
+        denominator: UnsignedValuePrecision,
+        sign: Sign,
+    ) -> Self {
+        assert!(denominator != 0);
 
         let hcf = hcf(numerator, denominator);
 
         RationalNumber {
             numerator: numerator / hcf,
             denominator: denominator / hcf,
-            sign: Sign::from(is_denominator_negative == is_numerator_negative),
+            sign: if numerator == 0 { Sign::Positive } else { sign },
+        }
+    }
+
+    /// Converts a wide intermediate result back into a rational number, if it fits.
+    fn from_wide(numerator: i128, denominator: i128) -> Option<Self> {
+        if denominator == 0 {
+            return None;
         }
+
+        let sign = Sign::from((numerator < 0) == (denominator < 0));
+        let numerator = UnsignedValuePrecision::try_from(numerator.unsigned_abs()).ok()?;
+        let denominator = UnsignedValuePrecision::try_from(denominator.unsigned_abs()).ok()?;
+
+        Some(Self::from_parts(numerator, denominator, sign))
+    }
+
+    fn wide_numerator(&self) -> i128 {
+        self.numerator as i128 * self.sign as i128
+    }
+
+    pub fn zero() -> Self {
+        Self::from_integer(0)
+    }
+
+    pub fn one() -> Self {
+        Self::from_integer(1)
+    }
+
+    pub fn from_integer(value: UnsignedValuePrecision) -> Self {
+        Self::from_parts(value, 1, Sign::Positive)
+    }
+
+    pub fn numerator(&self) -> UnsignedValuePrecision {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> UnsignedValuePrecision {
+        self.denominator
+    }
+
+    pub fn sign(&self) -> Sign {
+        self.sign
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator == 0
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denominator == 1
+    }
+
+    /// Returns `None` if the result is too large.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        // two u64s can multiply past the range of an i128, so even these can overflow
+        let numerator = self
+            .wide_numerator()
+            .checked_mul(rhs.denominator as i128)?
+            .checked_add(rhs.wide_numerator().checked_mul(self.denominator as i128)?)?;
+        let denominator = (self.denominator as i128).checked_mul(rhs.denominator as i128)?;
+
+        Self::from_wide(numerator, denominator)
+    }
+
+    /// Returns `None` if the result is too large.
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        let numerator = self.wide_numerator().checked_mul(rhs.wide_numerator())?;
+        let denominator = (self.denominator as i128).checked_mul(rhs.denominator as i128)?;
+
+        Self::from_wide(numerator, denominator)
+    }
+
+    /// Returns `None` when dividing by zero or if the result is too large.
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        self.checked_mul(rhs.reciprocal()?)
+    }
+
+    pub fn reciprocal(self) -> Option<Self> {
+        if self.is_zero() {
+            None
+        } else {
+            Some(Self::from_parts(
+                self.denominator,
+                self.numerator,
+                self.sign,
+            ))
+        }
+    }
+
+    pub fn to_float(self) -> FloatingPointPrecison {
+        let magnitude =
+            self.numerator as FloatingPointPrecison / self.denominator as FloatingPointPrecison;
+        magnitude * self.sign as isize as FloatingPointPrecison
     }
 }
 
-impl ToString for RationalNumber {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for RationalNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let sign = match self.sign {
             Sign::Positive => "",
             Sign::Negative => "-",
         };
 
-        format!("{}{}/{}", sign, self.numerator, self.denominator)
+        write!(f, "{}{}/{}", sign, self.numerator, self.denominator)
     }
 }
 
 impl Add for RationalNumber {
-    type Output = Self;
+    /// `None` if the sum is too large.
+    type Output = Option<Self>;
 
     fn add(self, rhs: Self) -> Self::Output {
-        let self_like_numerator = self.numerator * rhs.denominator;
-        let rhs_like_numerator = rhs.numerator * self.denominator;
-
-        let numerator = if self.sign == rhs.sign {
-            self_like_numerator + rhs_like_numerator
-        } else {
-            let (larger_numerator, smaller_numerator) = if self_like_numerator > rhs_like_numerator
-            {
-                (self_like_numerator, rhs_like_numerator)
-            } else {
-                (rhs_like_numerator, self_like_numerator)
-            };
-
-            larger_numerator - smaller_numerator
-        };
-
-        let denominator = self.denominator * rhs.denominator;
+        self.checked_add(rhs)
+    }
+}
 
-        let hcf = hcf(numerator, denominator);
+impl Neg for RationalNumber {
+    type Output = Self;
 
-        Self {
-            numerator: numerator / hcf,
-            denominator: denominator / hcf,
-            sign: if self_like_numerator > rhs_like_numerator {
-                self.sign
-            } else {
-                rhs.sign
-            },
-        }
+    fn neg(self) -> Self::Output {
+        Self::from_parts(self.numerator, self.denominator, -self.sign)
     }
 }
 
@@ -104,6 +189,33 @@ mod tests {
         let a = RationalNumber::new(3, 4);
         let b = RationalNumber::new(4, 3);
         let sum = a + b;
-        assert_eq!(sum, RationalNumber::new(25, 12));
+        assert_eq!(sum, Some(RationalNumber::new(25, 12)));
+
+        let difference = RationalNumber::new(1, 2) + RationalNumber::new(-3, 4);
+        assert_eq!(difference, Some(RationalNumber::new(-1, 4)));
+    }
+
+    #[test]
+    fn multiplying_and_dividing_rational_numbers_works() {
+        let a = RationalNumber::new(3, 4);
+        let b = RationalNumber::new(-2, 3);
+
+        assert_eq!(a.checked_mul(b), Some(RationalNumber::new(-1, 2)));
+        assert_eq!(a.checked_div(b), Some(RationalNumber::new(-9, 8)));
+        assert_eq!(a.checked_div(RationalNumber::zero()), None);
+    }
+
+    #[test]
+    fn overflowing_rational_arithmetic_gives_none() {
+        let largest = RationalNumber::from_integer(u64::MAX);
+
+        assert_eq!(largest.checked_mul(largest), None);
+        assert_eq!(largest + largest, None);
+        assert_eq!(
+            largest
+                .reciprocal()
+                .and_then(|smallest| smallest.checked_mul(smallest)),
+            None
+        );
     }
 }
@@ -0,0 +1,181 @@
+use super::{
+    calculation_precision::{FloatingPointPrecison, UnsignedValuePrecision},
+    helpers::{hcf, lcm, prime_factors},
+};
+
+/// The nth root of a positive integer, always kept in its simplest form: no
+/// factor of the radicand can be taken out of the root, and the degree is as
+/// small as possible (eg: ⁴√4 is stored as √2).
+///
+/// A radicand of 1 means there is no root at all.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Surd {
+    degree: u32,
+    radicand: UnsignedValuePrecision,
+}
+
+impl Surd {
+    pub fn one() -> Self {
+        Surd {
+            degree: 1,
+            radicand: 1,
+        }
+    }
+
+    /// Simplifies ⁿ√radicand into a whole number multiplied by a surd, eg:
+    /// ∛54 = 3∛2. Returns None if the degree is zero or on overflow.
+    pub fn new(
+        degree: u32,
+        radicand: UnsignedValuePrecision,
+    ) -> Option<(UnsignedValuePrecision, Self)> {
+        if degree == 0 {
+            return None;
+        }
+
+        if radicand == 0 {
+            return Some((0, Surd::one()));
+        }
+
+        let mut outside_the_root: UnsignedValuePrecision = 1;
+        let mut inside_the_root = vec![];
+
+        for (prime, multiplicity) in prime_factors(radicand) {
+            outside_the_root =
+                outside_the_root.checked_mul(prime.checked_pow(multiplicity / degree)?)?;
+
+            if multiplicity % degree != 0 {
+                inside_the_root.push((prime, multiplicity % degree));
+            }
+        }
+
+        // eg: ⁶√(2² * 3⁴) = ∛(2 * 3²)
+        let common_factor = inside_the_root.iter().fold(
+            degree as UnsignedValuePrecision,
+            |common, (_, multiplicity)| hcf(common, *multiplicity as UnsignedValuePrecision),
+        ) as u32;
+
+        let mut radicand: UnsignedValuePrecision = 1;
+
+        for (prime, multiplicity) in inside_the_root {
+            radicand = radicand.checked_mul(prime.checked_pow(multiplicity / common_factor)?)?;
+        }
+
+        Some((
+            outside_the_root,
+            Surd {
+                degree: degree / common_factor,
+                radicand,
+            },
+        ))
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.radicand == 1
+    }
+
+    pub fn degree(&self) -> u32 {
+        self.degree
+    }
+
+    /// Multiplies two surds by bringing them under a common root, eg:
+    /// √2 * ∛2 = ⁶√(2³ * 2²) = ⁶√32.
+    pub fn checked_mul(self, rhs: Self) -> Option<(UnsignedValuePrecision, Self)> {
+        let degree = lcm(
+            self.degree as UnsignedValuePrecision,
+            rhs.degree as UnsignedValuePrecision,
+        )?;
+        let degree = u32::try_from(degree).ok()?;
+
+        let radicand = self
+            .radicand
+            .checked_pow(degree / self.degree)?
+            .checked_mul(rhs.radicand.checked_pow(degree / rhs.degree)?)?;
+
+        Surd::new(degree, radicand)
+    }
+
+    /// Takes the nth root of the surd: ⁿ√(ᵐ√r) = ⁿᵐ√r.
+    pub fn checked_root(self, degree: u32) -> Option<(UnsignedValuePrecision, Self)> {
+        Surd::new(self.degree.checked_mul(degree)?, self.radicand)
+    }
+
+    /// Rationalises 1/ⁿ√r into ⁿ√(rⁿ⁻¹)/r, returning the surd and the whole
+    /// number to divide it by.
+    pub fn reciprocal(self) -> Option<(Self, UnsignedValuePrecision)> {
+        let (outside_the_root, surd) =
+            Surd::new(self.degree, self.radicand.checked_pow(self.degree - 1)?)?;
+
+        // rⁿ⁻¹ has no nth powers other than those from r, so nothing is lost
+        // by dividing the whole number out
+        let divisor = self.radicand / outside_the_root;
+
+        Some((surd, divisor))
+    }
+
+    pub fn to_float(self) -> FloatingPointPrecison {
+        (self.radicand as FloatingPointPrecison).powf(1.0 / self.degree as FloatingPointPrecison)
+    }
+}
+
+impl std::fmt::Display for Surd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_one() {
+            return Ok(());
+        }
+
+        let root = match self.degree {
+            2 => "√".to_string(),
+            3 => "∛".to_string(),
+            4 => "∜".to_string(),
+            degree => {
+                const SUPERSCRIPTS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+                let superscript: String = degree
+                    .to_string()
+                    .chars()
+                    .filter_map(|digit| digit.to_digit(10))
+                    .map(|digit| SUPERSCRIPTS[digit as usize])
+                    .collect();
+
+                format!("{superscript}√")
+            }
+        };
+
+        write!(f, "{root}{}", self.radicand)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn surds_are_simplified() {
+        assert_eq!(Surd::new(2, 12), Some((2, Surd::new(2, 3).unwrap().1)));
+        assert_eq!(Surd::new(3, 54).unwrap().0, 3);
+        assert_eq!(Surd::new(3, 54).unwrap().1.to_string(), "∛2");
+        assert_eq!(Surd::new(4, 4).unwrap().1.to_string(), "√2");
+        assert_eq!(Surd::new(2, 16), Some((4, Surd::one())));
+        assert_eq!(Surd::new(5, 3).unwrap().1.to_string(), "⁵√3");
+    }
+
+    #[test]
+    fn multiplying_surds_works() {
+        let root_two = Surd::new(2, 2).unwrap().1;
+        let cube_root_two = Surd::new(3, 2).unwrap().1;
+
+        assert_eq!(root_two.checked_mul(root_two), Some((2, Surd::one())));
+        assert_eq!(
+            root_two.checked_mul(cube_root_two).unwrap().1.to_string(),
+            "⁶√32"
+        );
+    }
+
+    #[test]
+    fn rationalising_surds_works() {
+        // 1/∛4 = ∛2/2
+        let (surd, divisor) = Surd::new(3, 4).unwrap().1.reciprocal().unwrap();
+        assert_eq!(surd.to_string(), "∛2");
+        assert_eq!(divisor, 2);
+    }
+}
@@ -0,0 +1,367 @@
+// units, and the dimensions of the quantities they measure (eg: km/h
+// measures a length per time). Angles are measured in radians like any other
+// unit, but follow rules of their own (see Inexact)
+
+use crate::{
+    input_parsing::{erasable::Erasable, locale::Locale, tokenizer::tokenize_after},
+    shared::errors::CalculationError,
+};
+
+use super::{
+    calculation_precision::FloatingPointPrecison,
+    calculator::{AngleUnit, Expression},
+    environment::Environment,
+    inexact::quantity_in,
+    parsers::parse_into_expression,
+};
+
+// the SI base units, in the order their powers are kept in, and then the
+// radian, which SI counts as a number but is kept so angles can be told apart
+const BASE_UNITS: [&str; 8] = ["m", "kg", "s", "A", "K", "mol", "cd", "rad"];
+
+// where the radian's power is kept
+const ANGLE_PLACE: usize = 7;
+
+/// The powers of the SI base units (m, kg, s, A, K, mol and cd) and radians
+/// a quantity is measured in, eg: m/s is m^1 s^-1 and rad/s is rad^1 s^-1.
+/// Numbers have none.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub(crate) struct Dimensions([i8; 8]);
+
+impl Dimensions {
+    const LENGTH: Dimensions = Dimensions([1, 0, 0, 0, 0, 0, 0, 0]);
+    const MASS: Dimensions = Dimensions([0, 1, 0, 0, 0, 0, 0, 0]);
+    const TIME: Dimensions = Dimensions([0, 0, 1, 0, 0, 0, 0, 0]);
+    const CURRENT: Dimensions = Dimensions([0, 0, 0, 1, 0, 0, 0, 0]);
+    const TEMPERATURE: Dimensions = Dimensions([0, 0, 0, 0, 1, 0, 0, 0]);
+    const AMOUNT: Dimensions = Dimensions([0, 0, 0, 0, 0, 1, 0, 0]);
+    pub(crate) const ANGLE: Dimensions = Dimensions([0, 0, 0, 0, 0, 0, 0, 1]);
+    const VOLUME: Dimensions = Dimensions([3, 0, 0, 0, 0, 0, 0, 0]);
+    const FREQUENCY: Dimensions = Dimensions([0, 0, -1, 0, 0, 0, 0, 0]);
+    const FORCE: Dimensions = Dimensions([1, 1, -2, 0, 0, 0, 0, 0]);
+    const ENERGY: Dimensions = Dimensions([2, 1, -2, 0, 0, 0, 0, 0]);
+    const POWER: Dimensions = Dimensions([2, 1, -3, 0, 0, 0, 0, 0]);
+    const PRESSURE: Dimensions = Dimensions([-1, 1, -2, 0, 0, 0, 0, 0]);
+    const VOLTAGE: Dimensions = Dimensions([2, 1, -3, -1, 0, 0, 0, 0]);
+    const CHARGE: Dimensions = Dimensions([0, 0, 1, 1, 0, 0, 0, 0]);
+    const CAPACITANCE: Dimensions = Dimensions([-2, -1, 4, 2, 0, 0, 0, 0]);
+
+    /// Whether this is a plain number's, which has no dimensions.
+    pub(crate) fn is_none(&self) -> bool {
+        self.0.iter().all(|power| *power == 0)
+    }
+
+    /// The power of radians, eg: 1 for rad/s.
+    pub(crate) fn angle_power(&self) -> i8 {
+        self.0[ANGLE_PLACE]
+    }
+
+    /// The same, but with radians to the given power, eg: rad/s from 1/s.
+    pub(crate) fn with_angle_power(mut self, power: i8) -> Dimensions {
+        self.0[ANGLE_PLACE] = power;
+        self
+    }
+
+    pub(crate) fn times(self, rhs: Dimensions) -> Result<Dimensions, CalculationError> {
+        self.combine(rhs, i8::checked_add)
+    }
+
+    pub(crate) fn divided_by(self, rhs: Dimensions) -> Result<Dimensions, CalculationError> {
+        self.combine(rhs, i8::checked_sub)
+    }
+
+    pub(crate) fn pow(self, exponent: i8) -> Result<Dimensions, CalculationError> {
+        self.combine(self, |power, _| power.checked_mul(exponent))
+    }
+
+    // None if a power doesn't divide evenly, eg: the square root of m^3
+    pub(crate) fn root(self, degree: i8) -> Option<Dimensions> {
+        if degree == 0
+            || self
+                .0
+                .iter()
+                .any(|power| power.checked_rem(degree) != Some(0))
+        {
+            return None;
+        }
+
+        self.combine(self, |power, _| power.checked_div(degree))
+            .ok()
+    }
+
+    // the powers combined pairwise, or an error if one doesn't fit, eg: m^100
+    // times m^100
+    fn combine(
+        self,
+        rhs: Dimensions,
+        combine: impl Fn(i8, i8) -> Option<i8>,
+    ) -> Result<Dimensions, CalculationError> {
+        let mut powers = [0; BASE_UNITS.len()];
+
+        for (power, (lhs, rhs)) in powers.iter_mut().zip(self.0.into_iter().zip(rhs.0)) {
+            *power = combine(lhs, rhs).ok_or_else(exponent_too_large)?;
+        }
+
+        Ok(Dimensions(powers))
+    }
+
+    // the base units with their powers, eg: m^2, for the powers picked out
+    fn base_units(&self, pick: impl Fn(i8) -> Option<u8>) -> Vec<String> {
+        BASE_UNITS
+            .iter()
+            .zip(self.0)
+            .filter_map(|(unit, power)| match pick(power)? {
+                1 => Some(unit.to_string()),
+                power => Some(format!("{unit}^{power}")),
+            })
+            .collect()
+    }
+}
+
+// in base units, eg: kg m/s^2, and empty for numbers
+impl std::fmt::Display for Dimensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // i8::MIN has no negation, so the sizes of the powers are taken instead
+        let above = self.base_units(|power| (power > 0).then_some(power.unsigned_abs()));
+        let below = self.base_units(|power| (power < 0).then_some(power.unsigned_abs()));
+
+        let mut symbol = above.join(" ");
+
+        if !below.is_empty() {
+            if symbol.is_empty() {
+                symbol.push('1');
+            }
+
+            match below.as_slice() {
+                [unit] => symbol.push_str(&format!("/{unit}")),
+                units => symbol.push_str(&format!("/({})", units.join(" "))),
+            }
+        }
+
+        f.write_str(&symbol)
+    }
+}
+
+/// The error for a power of a unit that's too large to keep track of, eg:
+/// m^200.
+pub(crate) fn exponent_too_large() -> CalculationError {
+    CalculationError::new("unit exponent too large".to_string())
+}
+
+/// What a unit erasable (with its prefix, if it has one) stands for, eg: km
+/// is 1000 of m.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Unit {
+    pub(crate) factor: FloatingPointPrecison,
+    // where a reading's zero is (in SI units), eg: 0 °C is 273.15 K
+    pub(crate) offset: FloatingPointPrecison,
+    pub(crate) dimensions: Dimensions,
+}
+
+// the angle units, which don't take prefixes
+const ANGLE_UNITS: [AngleUnit; 4] = [
+    AngleUnit::Radians,
+    AngleUnit::Degrees,
+    AngleUnit::Gradians,
+    AngleUnit::Turns,
+];
+
+impl Unit {
+    /// The unit, eg: `km` for the kilo prefix and metres. None if the
+    /// erasable isn't a unit, or the unit can't take the prefix.
+    pub(crate) fn build(prefix: Option<&Erasable>, unit: &Erasable) -> Option<Unit> {
+        use Erasable::*;
+
+        let (factor, offset, dimensions) = match unit {
+            Radians if prefix.is_none() => (1.0, 0.0, Dimensions::ANGLE),
+            Degrees if prefix.is_none() => {
+                (AngleUnit::Degrees.to_radians(1.0), 0.0, Dimensions::ANGLE)
+            }
+            Gradians if prefix.is_none() => {
+                (AngleUnit::Gradians.to_radians(1.0), 0.0, Dimensions::ANGLE)
+            }
+            Turns if prefix.is_none() => (AngleUnit::Turns.to_radians(1.0), 0.0, Dimensions::ANGLE),
+            Metre => (1.0, 0.0, Dimensions::LENGTH),
+            Gram => (1e-3, 0.0, Dimensions::MASS),
+            Second => (1.0, 0.0, Dimensions::TIME),
+            Ampere => (1.0, 0.0, Dimensions::CURRENT),
+            Kelvin => (1.0, 0.0, Dimensions::TEMPERATURE),
+            Mole => (1.0, 0.0, Dimensions::AMOUNT),
+            Litre => (1e-3, 0.0, Dimensions::VOLUME),
+            Hertz => (1.0, 0.0, Dimensions::FREQUENCY),
+            Newton => (1.0, 0.0, Dimensions::FORCE),
+            Joule => (1.0, 0.0, Dimensions::ENERGY),
+            Watt => (1.0, 0.0, Dimensions::POWER),
+            Pascal => (1.0, 0.0, Dimensions::PRESSURE),
+            Volt => (1.0, 0.0, Dimensions::VOLTAGE),
+            Calorie => (4.184, 0.0, Dimensions::ENERGY),
+            Coulomb => (1.0, 0.0, Dimensions::CHARGE),
+            Farad => (1.0, 0.0, Dimensions::CAPACITANCE),
+            // these don't take prefixes
+            Minute if prefix.is_none() => (60.0, 0.0, Dimensions::TIME),
+            Hour if prefix.is_none() => (3600.0, 0.0, Dimensions::TIME),
+            Day if prefix.is_none() => (86400.0, 0.0, Dimensions::TIME),
+            Celsius if prefix.is_none() => (1.0, 273.15, Dimensions::TEMPERATURE),
+            Fahrenheit if prefix.is_none() => {
+                (5.0 / 9.0, 459.67 * 5.0 / 9.0, Dimensions::TEMPERATURE)
+            }
+            Mile if prefix.is_none() => (1609.344, 0.0, Dimensions::LENGTH),
+            Yard if prefix.is_none() => (0.9144, 0.0, Dimensions::LENGTH),
+            Foot if prefix.is_none() => (0.3048, 0.0, Dimensions::LENGTH),
+            Inch if prefix.is_none() => (0.0254, 0.0, Dimensions::LENGTH),
+            Pound if prefix.is_none() => (0.45359237, 0.0, Dimensions::MASS),
+            Ounce if prefix.is_none() => (0.028349523125, 0.0, Dimensions::MASS),
+            Gallon if prefix.is_none() => (3.785411784e-3, 0.0, Dimensions::VOLUME),
+            _ => return None,
+        };
+
+        let prefix = match prefix {
+            Some(prefix) => prefix_factor(prefix)?,
+            None => 1.0,
+        };
+
+        Some(Unit {
+            factor: factor * prefix,
+            offset,
+            dimensions,
+        })
+    }
+
+    /// The angle unit it is, if it's one, eg: degrees for deg.
+    pub(crate) fn angle_unit(&self) -> Option<AngleUnit> {
+        ANGLE_UNITS.into_iter().find(|unit| {
+            self.dimensions == Dimensions::ANGLE && self.factor == unit.to_radians(1.0)
+        })
+    }
+}
+
+/// How many of the unit a prefix stands for, eg: 1000 for kilo. None if the
+/// erasable isn't a prefix.
+pub(crate) fn prefix_factor(prefix: &Erasable) -> Option<FloatingPointPrecison> {
+    use Erasable::*;
+
+    let factor = match prefix {
+        Nano => 1e-9,
+        Micro => 1e-6,
+        Milli => 1e-3,
+        Centi => 1e-2,
+        Kilo => 1e3,
+        Mega => 1e6,
+        Giga => 1e9,
+        _ => return None,
+    };
+
+    Some(factor)
+}
+
+/// Converts `value` from one unit into another, written as they're typed
+/// after `->` (eg: `km/h` into `m/s`, or `°F` into `°C`). Angles are taken to
+/// be in radians when converting plain numbers.
+pub fn convert(
+    value: FloatingPointPrecison,
+    from: &str,
+    to: &str,
+) -> Result<FloatingPointPrecison, CalculationError> {
+    let environment = Environment::new();
+
+    quantity_in(value, &parse_units(from)?, &environment)?
+        .in_units_of(&parse_units(to)?, &environment)
+}
+
+// eg: km/h, read as it would be after ->
+fn parse_units(units: &str) -> Result<Expression, CalculationError> {
+    let unknown = || CalculationError::new(format!("unknown units {units:?}"));
+
+    let erasables = tokenize_after(units, &[Erasable::ConversionArrow], Locale::default())
+        .map_err(|_| unknown())?;
+
+    match parse_into_expression(erasables.iter()) {
+        Ok(expression) if !expression.is_empty() => Ok(expression),
+        _ => Err(unknown()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dimensions_are_combined_and_shown() {
+        let speed = Dimensions::LENGTH.divided_by(Dimensions::TIME).unwrap();
+        assert_eq!(speed.to_string(), "m/s");
+        assert_eq!(Dimensions::FORCE.to_string(), "m kg/s^2");
+        assert_eq!(Dimensions::FREQUENCY.to_string(), "1/s");
+        assert_eq!(Dimensions::VOLTAGE.to_string(), "m^2 kg/(s^3 A)");
+
+        assert_eq!(speed.times(Dimensions::TIME).unwrap(), Dimensions::LENGTH);
+        assert_eq!(Dimensions::LENGTH.pow(3).unwrap(), Dimensions::VOLUME);
+        assert_eq!(Dimensions::VOLUME.root(3), Some(Dimensions::LENGTH));
+        assert_eq!(Dimensions::VOLUME.root(2), None);
+        assert!(speed.divided_by(speed).unwrap().is_none());
+
+        let angular_speed = Dimensions::ANGLE.divided_by(Dimensions::TIME).unwrap();
+        assert_eq!(angular_speed.to_string(), "rad/s");
+        assert_eq!(angular_speed.angle_power(), 1);
+        assert_eq!(angular_speed.with_angle_power(0), Dimensions::FREQUENCY);
+    }
+
+    #[test]
+    fn powers_too_large_for_dimensions_are_errors() {
+        let largest = Dimensions::LENGTH.pow(i8::MAX).unwrap();
+        let smallest = Dimensions::LENGTH.pow(i8::MIN).unwrap();
+
+        assert_eq!(largest.to_string(), "m^127");
+        assert_eq!(smallest.to_string(), "1/m^128");
+        assert!(largest.times(Dimensions::LENGTH).is_err());
+        assert!(smallest.divided_by(Dimensions::LENGTH).is_err());
+        assert!(smallest.pow(-1).is_err());
+        assert_eq!(smallest.root(-1), None);
+    }
+
+    #[test]
+    fn units_are_built_with_their_prefixes() {
+        assert_eq!(
+            Unit::build(Some(&Erasable::Kilo), &Erasable::Gram),
+            Some(Unit {
+                factor: 1.0,
+                offset: 0.0,
+                dimensions: Dimensions::MASS
+            })
+        );
+        assert_eq!(
+            Unit::build(None, &Erasable::Degrees).and_then(|unit| unit.angle_unit()),
+            Some(AngleUnit::Degrees)
+        );
+        assert_eq!(
+            Unit::build(None, &Erasable::Metre).unwrap().angle_unit(),
+            None
+        );
+        assert_eq!(Unit::build(Some(&Erasable::Kilo), &Erasable::Hour), None);
+        assert_eq!(Unit::build(Some(&Erasable::Kilo), &Erasable::Degrees), None);
+        assert_eq!(Unit::build(None, &Erasable::Pi), None);
+    }
+
+    #[test]
+    fn temperatures_and_other_units_are_converted() {
+        let converted = |value, from, to| format!("{:.4}", convert(value, from, to).unwrap());
+
+        assert_eq!(converted(100.0, "°F", "°C"), "37.7778");
+        assert_eq!(converted(-40.0, "degC", "degF"), "-40.0000");
+        assert_eq!(converted(300.0, "K", "°C"), "26.8500");
+        assert_eq!(converted(1.0, "F", "µF"), "1000000.0000");
+        assert_eq!(converted(2.0, "A s", "C"), "2.0000");
+        assert_eq!(converted(1.0, "mi", "km"), "1.6093");
+        assert_eq!(converted(30.0, "deg", "rad"), "0.5236");
+        assert_eq!(converted(1.0, "turn/min", "deg/s"), "6.0000");
+        assert_eq!(converted(1.0, "lb", "oz"), "16.0000");
+
+        assert_eq!(
+            convert(1.0, "m", "s").unwrap_err().to_string(),
+            "error: can't convert m into s"
+        );
+        assert_eq!(
+            convert(1.0, "#", "m").unwrap_err().to_string(),
+            "error: unknown units \"#\""
+        );
+    }
+}
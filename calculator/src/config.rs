@@ -0,0 +1,468 @@
+// the user's preferences, read at startup from config.toml in their config
+// directory (eg: ~/.config/calculator/config.toml on linux)
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use num_traits::ToPrimitive;
+use serde::Deserialize;
+use strum::IntoEnumIterator;
+
+use crate::{
+    calculation::{
+        calculator::{AngleUnit, OutputMode, OutputSettings, WordSize},
+        formatting::{Notation, Precision},
+    },
+    highlighting::Theme,
+    input_parsing::{erasable::Erasable, locale::Locale},
+    shared::errors::ConfigError,
+};
+
+// the file as it's written, before it's checked
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    locale: Option<String>,
+    angle_unit: Option<String>,
+    word_size: Option<String>,
+    output_mode: Option<String>,
+    notation: Option<String>,
+    decimal_places: Option<usize>,
+    significant_figures: Option<usize>,
+    digit_grouping: Option<String>,
+    decimal_separator: Option<String>,
+    colours: HashMap<String, String>,
+    keys: HashMap<String, String>,
+}
+
+/// What a key does when pressed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    /// Types the erasable with this (default) key, eg: `s` for sin.
+    Type(char),
+    Help,
+    Quit,
+    /// Switches the session's angle unit.
+    NextAngleUnit,
+    /// Switches the session's word size, turning programmer mode on or off.
+    NextWordSize,
+    /// Switches the notation decimals are written in.
+    NextNotation,
+}
+
+impl Action {
+    fn name(&self) -> &'static str {
+        match self {
+            Action::Type(key) => match Erasable::build(*key) {
+                Ok(erasable) => (&erasable).into(),
+                Err(_) => "?",
+            },
+            Action::Help => "help",
+            Action::Quit => "quit",
+            Action::NextAngleUnit => "angle_unit",
+            Action::NextWordSize => "word_size",
+            Action::NextNotation => "notation",
+        }
+    }
+}
+
+/// Which key does what: every erasable and action has exactly one key.
+#[derive(Debug)]
+pub struct KeyBindings {
+    actions: HashMap<char, Action>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::build(&HashMap::new(), Locale::default()).unwrap()
+    }
+}
+
+impl KeyBindings {
+    // everything a key can be bound to, with its default key. The decimal
+    // point and comma are typed with the locale's separators
+    fn defaults(locale: Locale) -> Vec<(Action, char)> {
+        // units have no key of their own, so are only typed by name
+        let mut defaults: Vec<(Action, char)> = Erasable::iter()
+            .filter_map(|erasable| erasable.to_u8())
+            .filter(|key| key.is_ascii())
+            .map(|key| (Action::Type(key as char), locale.localised(key as char)))
+            .collect();
+
+        // these are kept free in Erasable's striminant attribute
+        defaults.push((Action::Help, 'h'));
+        defaults.push((Action::Quit, 'q'));
+        defaults.push((Action::NextAngleUnit, 'U'));
+        defaults.push((Action::NextWordSize, '@'));
+        defaults.push((Action::NextNotation, '#'));
+
+        defaults
+    }
+
+    /// Binds keys given as `name = key`, where the name is what's shown in
+    /// the help (eg: `sin` or `+`), `help`, `quit`, `angle_unit`, `word_size` or `notation`.
+    /// Anything not given keeps its default key, which two things can't share.
+    /// The decimal point and comma default to the locale's separators.
+    pub fn build(remapped: &HashMap<String, String>, locale: Locale) -> Result<Self, ConfigError> {
+        let defaults = Self::defaults(locale);
+        let mut keys: Vec<(Action, char)> = defaults.clone();
+
+        for (name, key) in remapped {
+            let Some(index) = defaults
+                .iter()
+                .position(|(action, _)| action.name() == name)
+            else {
+                return Err(ConfigError::new(format!(
+                    "there's nothing called {name} to give a key to"
+                )));
+            };
+
+            let mut chars = key.chars();
+            let (Some(key), None) = (chars.next(), chars.next()) else {
+                return Err(ConfigError::new(format!(
+                    "the key for {name} should be a single character, not \"{key}\""
+                )));
+            };
+
+            keys[index].1 = key;
+        }
+
+        let mut actions: HashMap<char, Action> = HashMap::new();
+
+        for (action, key) in keys {
+            if let Some(other) = actions.insert(key, action) {
+                // in the order they're listed in the help
+                let (first, second) = (other.name(), action.name());
+
+                return Err(ConfigError::new(format!(
+                    "{key} is the key for both {first} and {second}"
+                )));
+            }
+        }
+
+        Ok(Self { actions })
+    }
+
+    /// What pressing the key does. Keys that aren't bound to anything (after
+    /// remapping) are typed as they are.
+    pub fn action(&self, key: char) -> Action {
+        self.actions.get(&key).copied().unwrap_or(Action::Type(key))
+    }
+
+    /// The key that does the action.
+    pub fn key(&self, action: Action) -> Option<char> {
+        self.actions
+            .iter()
+            .find_map(|(key, bound)| (*bound == action).then_some(*key))
+    }
+
+    /// The key for what's called `name` in the config file, eg: `sin` or
+    /// `help`.
+    pub fn key_named(&self, name: &str) -> Option<char> {
+        self.actions
+            .iter()
+            .find_map(|(key, action)| (action.name() == name).then_some(*key))
+    }
+}
+
+/// Everything that can be set in the config file.
+#[derive(Default)]
+pub struct Config {
+    /// The separators input is typed and shown with.
+    pub locale: Locale,
+    /// The session's angle unit to start with.
+    pub angle_unit: AngleUnit,
+    /// The word size to start programmer mode in, if it starts in it.
+    pub word_size: Option<WordSize>,
+    pub output_settings: OutputSettings,
+    pub theme: Theme,
+    pub key_bindings: KeyBindings,
+}
+
+impl Config {
+    /// Where the config file is kept.
+    pub fn default_file() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("calculator").join("config.toml"))
+    }
+
+    /// Reads the config from the file, which doesn't have to exist.
+    pub fn load(file: &Path) -> Result<Self, ConfigError> {
+        match fs::read_to_string(file) {
+            Ok(text) => Self::parse(&text),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(ConfigError::new(e.to_string())),
+        }
+    }
+
+    /// Reads and checks the config, eg:
+    ///
+    /// ```toml
+    /// locale = "decimal_comma"
+    /// angle_unit = "deg"
+    /// word_size = "u8"
+    /// output_mode = "decimal"
+    /// notation = "scientific"
+    /// decimal_places = 4
+    /// digit_grouping = ","
+    ///
+    /// [colours]
+    /// function_name = "dark_green"
+    ///
+    /// [keys]
+    /// sin = "Z"
+    /// quit = "Q"
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let file: ConfigFile =
+            toml::from_str(text).map_err(|e| ConfigError::new(e.message().to_string()))?;
+
+        let locale = match file.locale.as_deref() {
+            None | Some("decimal_point") => Locale::DecimalPoint,
+            Some("decimal_comma") => Locale::DecimalComma,
+            Some(locale) => {
+                return Err(ConfigError::new(format!(
+                    "locale should be \"decimal_point\" or \"decimal_comma\", not \"{locale}\""
+                )))
+            }
+        };
+
+        let angle_unit = match file.angle_unit.as_deref() {
+            None | Some("rad") => AngleUnit::Radians,
+            Some("deg") => AngleUnit::Degrees,
+            Some("grad") => AngleUnit::Gradians,
+            Some("turn") => AngleUnit::Turns,
+            Some(angle_unit) => {
+                return Err(ConfigError::new(format!(
+                    "angle_unit should be \"rad\", \"deg\", \"grad\" or \"turn\", not \
+                     \"{angle_unit}\""
+                )))
+            }
+        };
+
+        let word_size = match file.word_size.as_deref() {
+            None => None,
+            Some(word_size) => Some(word_size.parse::<WordSize>().map_err(|()| {
+                ConfigError::new(format!(
+                    "word_size should be \"i8\", \"u8\", \"i16\", \"u16\", \"i32\", \"u32\", \
+                     \"i64\" or \"u64\", not \"{word_size}\""
+                ))
+            })?),
+        };
+
+        let mut output_settings = OutputSettings::default();
+
+        if let Some(output_mode) = file.output_mode {
+            output_settings.output_mode = match output_mode.as_str() {
+                "fraction" => OutputMode::Fraction,
+                "mixed_fraction" => OutputMode::MixedFraction,
+                "decimal" => OutputMode::Decimal,
+                "degrees_minutes_seconds" => OutputMode::DegreesMinutesSeconds,
+                "hexadecimal" => OutputMode::Hexadecimal,
+                "binary" => OutputMode::Binary,
+                "octal" => OutputMode::Octal,
+                _ => {
+                    return Err(ConfigError::new(format!(
+                        "output_mode should be \"fraction\", \"mixed_fraction\", \
+                         \"decimal\", \"degrees_minutes_seconds\", \"hexadecimal\", \
+                         \"binary\" or \"octal\", not \"{output_mode}\""
+                    )))
+                }
+            };
+        }
+
+        let number_format = &mut output_settings.number_format;
+
+        number_format.notation = match file.notation.as_deref() {
+            None | Some("standard") => Notation::Standard,
+            Some("scientific") => Notation::Scientific,
+            Some("engineering") => Notation::Engineering,
+            Some(notation) => {
+                return Err(ConfigError::new(format!(
+                    "notation should be \"standard\", \"scientific\" or \"engineering\", not \
+                     \"{notation}\""
+                )))
+            }
+        };
+
+        number_format.precision = match (file.decimal_places, file.significant_figures) {
+            (None, None) => Precision::Shortest,
+            (Some(places), None) => Precision::DecimalPlaces(places),
+            (None, Some(0)) => {
+                return Err(ConfigError::new(
+                    "significant_figures should be at least 1".to_string(),
+                ))
+            }
+            (None, Some(figures)) => Precision::SignificantFigures(figures),
+            (Some(_), Some(_)) => {
+                return Err(ConfigError::new(
+                    "only one of decimal_places and significant_figures can be given".to_string(),
+                ))
+            }
+        };
+
+        number_format.decimal_separator = locale.decimal_separator();
+
+        if let Some(separator) = file.decimal_separator {
+            number_format.decimal_separator = match separator.as_str() {
+                "." => '.',
+                "," => ',',
+                _ => {
+                    return Err(ConfigError::new(format!(
+                        "decimal_separator should be \".\" or \",\", not \"{separator}\""
+                    )))
+                }
+            };
+        }
+
+        if let Some(grouping) = file.digit_grouping {
+            let mut chars = grouping.chars();
+
+            number_format.digit_grouping = match (chars.next(), chars.next()) {
+                (Some(c), None) if c != number_format.decimal_separator && !c.is_ascii_digit() => {
+                    Some(c)
+                }
+                _ => {
+                    return Err(ConfigError::new(format!(
+                        "digit_grouping should be a single character other than a digit or the \
+                         decimal separator, not \"{grouping}\""
+                    )))
+                }
+            };
+        }
+
+        let mut theme = Theme::default();
+
+        for (name, colour) in &file.colours {
+            theme.set_colour(name, colour).map_err(|e| {
+                let e = e.to_string();
+                let e = e.trim_start_matches("error: ");
+                ConfigError::new(format!("in [colours], {e}"))
+            })?;
+        }
+
+        Ok(Self {
+            locale,
+            angle_unit,
+            word_size,
+            output_settings,
+            theme,
+            key_bindings: KeyBindings::build(&file.keys, locale)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_config_is_read() {
+        let config = Config::parse(
+            r#"
+            angle_unit = "deg"
+            word_size = "u16"
+            output_mode = "mixed_fraction"
+            notation = "engineering"
+            decimal_places = 2
+            digit_grouping = " "
+
+            [colours]
+            digit = "red"
+
+            [keys]
+            sin = "Z"
+            quit = "Q"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.angle_unit, AngleUnit::Degrees);
+        assert_eq!(
+            config
+                .word_size
+                .map(|word_size| word_size.to_string())
+                .as_deref(),
+            Some("u16")
+        );
+        assert_eq!(
+            config.output_settings.output_mode,
+            OutputMode::MixedFraction
+        );
+        let number_format = config.output_settings.number_format;
+        assert_eq!(number_format.notation, Notation::Engineering);
+        assert_eq!(number_format.precision, Precision::DecimalPlaces(2));
+        assert_eq!(number_format.digit_grouping, Some(' '));
+        assert_eq!(number_format.decimal_separator, '.');
+
+        let keys = config.key_bindings;
+        assert_eq!(keys.action('Z'), Action::Type('s'));
+        assert_eq!(keys.action('Q'), Action::Quit);
+        assert_eq!(keys.key(Action::Type('s')), Some('Z'));
+        // keys that were given away are typed as they are
+        assert_eq!(keys.action('q'), Action::Type('q'));
+        assert_eq!(keys.action('h'), Action::Help);
+
+        assert!(Config::parse("").is_ok());
+    }
+
+    #[test]
+    fn a_decimal_comma_changes_the_separators() {
+        let config = Config::parse("locale = \"decimal_comma\"").unwrap();
+
+        assert_eq!(config.locale, Locale::DecimalComma);
+        assert_eq!(config.output_settings.number_format.decimal_separator, ',');
+
+        let keys = config.key_bindings;
+        assert_eq!(keys.action(','), Action::Type('.'));
+        assert_eq!(keys.action(';'), Action::Type(','));
+        // a decimal point is still a decimal point
+        assert_eq!(keys.action('.'), Action::Type('.'));
+    }
+
+    #[test]
+    fn mistakes_in_the_config_are_explained() {
+        let error = |text: &str| Config::parse(text).err().unwrap().to_string();
+
+        assert_eq!(
+            error("[keys]\nsin = \"c\""),
+            "error in the config file: c is the key for both sin and cos"
+        );
+        assert!(error("[keys]\nsin = \"Z\"\ncos = \"Z\"").contains("Z is the key for both"));
+        // swapping keys is fine
+        assert!(Config::parse("[keys]\nsin = \"c\"\ncos = \"s\"").is_ok());
+
+        assert_eq!(
+            error("[keys]\nsine = \"Z\""),
+            "error in the config file: there's nothing called sine to give a key to"
+        );
+        assert_eq!(
+            error("[keys]\nsin = \"zz\""),
+            "error in the config file: the key for sin should be a single character, not \"zz\""
+        );
+        assert_eq!(
+            error("angle_unit = \"turns\""),
+            "error in the config file: angle_unit should be \"rad\", \"deg\", \"grad\" or \"turn\", \
+             not \"turns\""
+        );
+        assert_eq!(
+            error("word_size = \"i128\""),
+            "error in the config file: word_size should be \"i8\", \"u8\", \"i16\", \"u16\", \
+             \"i32\", \"u32\", \"i64\" or \"u64\", not \"i128\""
+        );
+        assert!(error("[colours]\ndigits = \"red\"").contains("in [colours]"));
+        assert!(error("angle_units = \"deg\"").contains("unknown field"));
+        assert!(error("decimal_places = -1").contains("invalid value"));
+        assert_eq!(
+            error("decimal_places = 2\nsignificant_figures = 3"),
+            "error in the config file: only one of decimal_places and significant_figures can be \
+             given"
+        );
+        assert!(error("notation = \"sci\"").contains("notation should be"));
+        assert!(
+            error("decimal_separator = \",\"\ndigit_grouping = \",\"").contains("digit_grouping")
+        );
+    }
+}
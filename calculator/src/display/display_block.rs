@@ -40,9 +40,7 @@ impl DisplayBlock {
         let r_min = r.get_min();
         let r_max = r.get_max();
 
-        if let Err(e) = self.units.insert(r, unit) {
-            return Err(e);
-        }
+        self.units.insert(r, unit)?;
 
         if r_max > self.relative_line_placement.get_max() {
             self.relative_line_placement.set_max(r_max);
@@ -63,15 +61,13 @@ impl DisplayBlock {
     }
 }
 
-impl ToString for DisplayBlock {
-    fn to_string(&self) -> String {
-        let mut result = String::new();
-
+impl std::fmt::Display for DisplayBlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for (_, unit) in self.units.sorted() {
-            result.push_str(&unit.to_string());
+            unit.fmt(f)?;
         }
 
-        result
+        Ok(())
     }
 }
 
@@ -11,11 +11,11 @@ pub enum DisplayUnit {
     DisplayBlock(DisplayBlock),
 }
 
-impl ToString for DisplayUnit {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for DisplayUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            DisplayUnit::DisplayBlock(b) => b.to_string(),
-            DisplayUnit::DisplaySegment(s) => s.to_string(),
+            DisplayUnit::DisplayBlock(b) => b.fmt(f),
+            DisplayUnit::DisplaySegment(s) => s.fmt(f),
         }
     }
 }
@@ -1,6 +1,6 @@
-use std::{hash::Hash, ops::Sub};
+use std::hash::Hash;
 
-#[derive(Hash, PartialEq, Eq, Ord)]
+#[derive(Hash, PartialEq, Eq)]
 pub struct Range {
     // inclusive
     min: isize,
@@ -8,22 +8,28 @@ pub struct Range {
     max: isize,
 }
 
-impl PartialOrd for Range {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+impl Ord for Range {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         if self.overlaps(other) {
             panic!("cannot compare ranges that overlap");
         }
 
         if self.max < other.min {
-            Some(std::cmp::Ordering::Less)
+            std::cmp::Ordering::Less
         } else if self.min > other.max {
-            Some(std::cmp::Ordering::Greater)
+            std::cmp::Ordering::Greater
         } else {
-            Some(std::cmp::Ordering::Equal)
+            std::cmp::Ordering::Equal
         }
     }
 }
 
+impl PartialOrd for Range {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Range {
     pub fn new(min: isize, max: isize) -> Self {
         assert!(max >= min);
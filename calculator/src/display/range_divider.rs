@@ -2,7 +2,7 @@ use itertools::Itertools;
 
 use super::range::Range;
 use crate::shared::errors::MutationOperationError;
-use std::{collections::HashMap, hash::Hash, ops::Sub};
+use std::collections::HashMap;
 
 pub struct RangeEntry<V> {
     min: isize,
@@ -44,7 +44,7 @@ impl<V> RangeDivider<V> {
     }
 
     pub fn insert(&mut self, r: Range, content: V) -> Result<(), MutationOperationError> {
-        for (range, _) in &self.boundaries {
+        for range in self.boundaries.keys() {
             if range.overlaps(&r) {
                 return Err(MutationOperationError::AdditionError);
             }
@@ -70,7 +70,7 @@ impl<V> RangeDivider<V> {
     }
 
     pub fn sorted(&self) -> std::vec::IntoIter<(&Range, &V)> {
-        self.boundaries.iter().sorted_by_key(|x| (x.0))
+        self.boundaries.iter().sorted_by_key(|x| x.0)
     }
 }
 
@@ -97,10 +97,10 @@ mod tests {
     fn sorted_range_divider_works() {
         let mut divider = RangeDivider::new();
 
-        divider.insert(Range::new(0, 1), "HEY");
-        divider.insert(Range::new(3, 5), "LOVE");
-        divider.insert(Range::new(2, 2), "I");
-        divider.insert(Range::new(6, 10), "YOU");
+        divider.insert(Range::new(0, 1), "HEY").unwrap();
+        divider.insert(Range::new(3, 5), "LOVE").unwrap();
+        divider.insert(Range::new(2, 2), "I").unwrap();
+        divider.insert(Range::new(6, 10), "YOU").unwrap();
 
         let phrase_of_endearment: Vec<&str> = divider.sorted().map(|(_, s)| *s).collect();
         let phrase_of_endearment = phrase_of_endearment.join(" ");
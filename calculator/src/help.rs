@@ -0,0 +1,407 @@
+// the help, which is compiled in (so it works wherever the calculator is run
+// from) and shown in a pager that can be scrolled and searched
+
+use std::io::{self, stdout};
+
+use crossterm::{
+    cursor,
+    event::{read, Event, KeyCode},
+    execute, queue,
+    style::{Print, Stylize},
+    terminal,
+};
+use num_traits::ToPrimitive;
+use strum::IntoEnumIterator;
+
+use crate::{
+    config::{Action, KeyBindings},
+    input_parsing::erasable::{Erasable, ErasableType},
+};
+
+// the text around the lists of keys and units, which go where {keys} and
+// {units} are. Any other key is written as {key:name}, with the name it has
+// in the config file (eg: {key:help} or {key:ans}), so that it's as bound
+const HELP_TEXT: &str = include_str!("../help_text.txt");
+
+// everything that can be entered (that needs an introduction), with its key
+// and full name
+fn keys(key_bindings: &KeyBindings) -> String {
+    let lines: Vec<String> = Erasable::iter()
+        .filter_map(|erasable| {
+            let description = erasable.description()?;
+            let code = erasable.to_u8()? as char;
+            let key = key_bindings.key(Action::Type(code))?;
+            let name: &str = (&erasable).into();
+            // when it's more than the key itself
+            let name = if name.chars().ne([code]) { name } else { "" };
+
+            Some(format!("  {key}  {name:<9}{description}"))
+        })
+        .collect();
+
+    lines.join("\n")
+}
+
+// the units and prefixes, which are typed by name rather than a key
+fn units() -> String {
+    let lines: Vec<String> = Erasable::iter()
+        .filter(|erasable| ErasableType::from(erasable) == ErasableType::Unit)
+        .filter_map(|erasable| {
+            let description = erasable.description()?;
+            let name: &str = (&erasable).into();
+
+            Some(format!("  {name:<5}{description}"))
+        })
+        .collect();
+
+    lines.join("\n")
+}
+
+// the text with every {key:name} filled in
+fn with_keys(text: &str, key_bindings: &KeyBindings) -> String {
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{key:") {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+
+        filled.push_str(&rest[..start]);
+
+        match key_bindings.key_named(&rest[start + "{key:".len()..end]) {
+            Some(key) => filled.push(key),
+            None => filled.push_str(&rest[start..=end]),
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    filled.push_str(rest);
+    filled
+}
+
+/// The help, as shown by `h` or printed by `calculator --help`, listing the
+/// keys as they're bound.
+pub fn help_text(key_bindings: &KeyBindings) -> String {
+    with_keys(HELP_TEXT, key_bindings)
+        .replace("{keys}", &keys(key_bindings))
+        .replace("{units}", &units())
+}
+
+struct Pager {
+    lines: Vec<String>,
+    // the first line shown
+    top: usize,
+    // how many lines fit on the screen
+    height: usize,
+    query: String,
+    // the line of the match last found
+    found: Option<usize>,
+}
+
+impl Pager {
+    fn new(text: &str) -> Self {
+        Self {
+            lines: text.lines().map(|line| line.to_string()).collect(),
+            top: 0,
+            height: 1,
+            query: String::new(),
+            found: None,
+        }
+    }
+
+    fn last_top(&self) -> usize {
+        self.lines.len().saturating_sub(self.height)
+    }
+
+    fn scroll_by(&mut self, lines: isize) {
+        self.top = self.top.saturating_add_signed(lines).min(self.last_top());
+    }
+
+    fn scroll_to(&mut self, line: usize) {
+        self.top = line.min(self.last_top());
+    }
+
+    // searching ignores case
+    fn matches(&self, line: usize) -> bool {
+        self.lines[line]
+            .to_ascii_lowercase()
+            .contains(&self.query.to_ascii_lowercase())
+    }
+
+    // looks for the query from the top of the screen; returns false if
+    // there's no match
+    fn search(&mut self, query: String) -> bool {
+        self.query = query;
+        self.found = None;
+
+        self.find(self.top, true)
+    }
+
+    fn find_next(&mut self) -> bool {
+        match self.found {
+            Some(line) => self.find(line + 1, true),
+            None => self.find(self.top, true),
+        }
+    }
+
+    fn find_previous(&mut self) -> bool {
+        match self.found {
+            Some(line) => line > 0 && self.find(line - 1, false),
+            None => self.find(self.top, false),
+        }
+    }
+
+    fn find(&mut self, from: usize, forwards: bool) -> bool {
+        if self.query.is_empty() || from >= self.lines.len() {
+            return false;
+        }
+
+        let found = if forwards {
+            (from..self.lines.len()).find(|line| self.matches(*line))
+        } else {
+            (0..=from).rev().find(|line| self.matches(*line))
+        };
+
+        match found {
+            Some(line) => {
+                self.found = Some(line);
+                self.scroll_to(line);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // the line, cut to the screen's width, with any matches in reverse video
+    fn render_line(&self, line: usize, width: usize) -> String {
+        let text: String = self.lines[line].chars().take(width).collect();
+
+        if self.query.is_empty() {
+            return text;
+        }
+
+        let lowercase = text.to_ascii_lowercase();
+        let query = self.query.to_ascii_lowercase();
+        let mut rendered = String::new();
+        let mut rest = 0;
+
+        for (start, _) in lowercase.match_indices(&query) {
+            let end = start + query.len();
+
+            rendered.push_str(&text[rest..start]);
+            rendered.push_str(&text[start..end].reverse().to_string());
+            rest = end;
+        }
+
+        rendered.push_str(&text[rest..]);
+        rendered
+    }
+
+    fn draw(&self, status: &str, width: u16) -> io::Result<()> {
+        let mut stdout = stdout();
+
+        queue!(stdout, terminal::Clear(terminal::ClearType::All))?;
+
+        for (row, line) in (self.top..self.lines.len()).take(self.height).enumerate() {
+            queue!(
+                stdout,
+                cursor::MoveTo(0, row as u16),
+                Print(self.render_line(line, width as usize)),
+            )?;
+        }
+
+        execute!(
+            stdout,
+            cursor::MoveTo(0, self.height as u16),
+            Print(
+                status
+                    .chars()
+                    .take(width as usize)
+                    .collect::<String>()
+                    .dim()
+            ),
+        )
+    }
+}
+
+/// Shows the help in a pager (in raw mode): Up/Down and PageUp/PageDown
+/// scroll, / searches, n and N go to the next and previous match, and q or
+/// Esc closes it.
+pub fn show_help(key_bindings: &KeyBindings) -> io::Result<()> {
+    execute!(stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+
+    let result = page(&mut Pager::new(&help_text(key_bindings)));
+
+    execute!(stdout(), cursor::Show, terminal::LeaveAlternateScreen)?;
+
+    result
+}
+
+fn page(pager: &mut Pager) -> io::Result<()> {
+    const HINT: &str =
+        "Up/Down to scroll, / to search, n/N for the next/previous match, q to close";
+
+    let mut status = HINT.to_string();
+    // the search being typed in
+    let mut search: Option<String> = None;
+
+    loop {
+        let (width, height) = terminal::size()?;
+        pager.height = height.saturating_sub(1).max(1) as usize;
+        pager.scroll_by(0);
+
+        match &search {
+            Some(query) => pager.draw(&format!("/{query}"), width)?,
+            None => pager.draw(&status, width)?,
+        }
+
+        let Event::Key(event) = read()? else {
+            continue;
+        };
+
+        if let Some(query) = &mut search {
+            match event.code {
+                KeyCode::Char(c) => query.push(c),
+                KeyCode::Backspace => {
+                    query.pop();
+                }
+                KeyCode::Enter => {
+                    let query = search.take().unwrap_or_default();
+
+                    status = if pager.search(query.clone()) {
+                        HINT.to_string()
+                    } else {
+                        format!("no match for {query}")
+                    };
+                }
+                KeyCode::Esc => search = None,
+                _ => {}
+            }
+
+            continue;
+        }
+
+        let page = pager.height as isize;
+
+        match event.code {
+            KeyCode::Up | KeyCode::Char('k') => pager.scroll_by(-1),
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Enter => pager.scroll_by(1),
+            KeyCode::PageUp | KeyCode::Char('b') => pager.scroll_by(-page),
+            KeyCode::PageDown | KeyCode::Char(' ') => pager.scroll_by(page),
+            KeyCode::Home | KeyCode::Char('g') => pager.scroll_to(0),
+            KeyCode::End | KeyCode::Char('G') => pager.scroll_to(pager.lines.len()),
+            KeyCode::Char('/') => search = Some(String::new()),
+            KeyCode::Char('n') | KeyCode::Char('N') => {
+                let found = match event.code {
+                    KeyCode::Char('n') => pager.find_next(),
+                    _ => pager.find_previous(),
+                };
+
+                status = if found {
+                    HINT.to_string()
+                } else {
+                    format!("no more matches for {}", pager.query)
+                };
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        calculation::calculator::Calculator,
+        input_parsing::{erasable_cluster::ErasableCluster, locale::Locale},
+    };
+
+    use super::*;
+
+    #[test]
+    fn the_help_lists_every_function() {
+        let help = help_text(&KeyBindings::default());
+
+        assert!(!help.contains("{keys}"));
+        assert!(help.contains("  s  sin      sine: sin(angle)"));
+        assert!(help.contains("  R  NthRoot  nth root"));
+        assert!(help.contains("  p  pi       pi"));
+        // digits need no introduction
+        assert!(!help.contains("  1  "));
+
+        let mut remapped = std::collections::HashMap::new();
+        remapped.insert("sin".to_string(), "Z".to_string());
+        let help = help_text(&KeyBindings::build(&remapped, Locale::default()).unwrap());
+        assert!(help.contains("  Z  sin      sine: sin(angle)"));
+
+        let keys = KeyBindings::build(&Default::default(), Locale::DecimalComma).unwrap();
+        assert!(help_text(&keys).contains("  ;           separates a function's arguments"));
+    }
+
+    #[test]
+    fn the_examples_in_the_list_can_be_entered() {
+        for erasable in Erasable::iter() {
+            let Some((_, example)) = erasable
+                .description()
+                .and_then(|description| description.split_once("eg: "))
+            else {
+                continue;
+            };
+
+            // what's entered, rather than what it comes to or another way of
+            // writing it, eg: the 1_2 of 1_2 is 1/2
+            let example = [" = ", " is ", " (or "]
+                .iter()
+                .fold(example, |example, end| {
+                    example.split(end).next().unwrap_or(example)
+                });
+
+            let cluster = ErasableCluster::build(example).unwrap();
+            assert!(Calculator::build(&cluster).is_ok(), "{example}");
+        }
+    }
+
+    #[test]
+    fn keys_in_the_help_are_as_bound() {
+        let help = help_text(&KeyBindings::default());
+
+        assert!(!help.contains("{key:"));
+        assert!(help.contains("Press # to switch how decimals are written"));
+        assert!(help.contains("press A for ans"));
+
+        let mut remapped = std::collections::HashMap::new();
+        remapped.insert("notation".to_string(), "Y".to_string());
+        remapped.insert("ans".to_string(), "Q".to_string());
+        let help = help_text(&KeyBindings::build(&remapped, Locale::default()).unwrap());
+
+        assert!(help.contains("Press Y to switch how decimals are written"));
+        assert!(help.contains("press Q for ans"));
+    }
+
+    #[test]
+    fn the_pager_scrolls_and_searches() {
+        let mut pager = Pager::new("one\ntwo\nthree\nfour\nTwenty-two\nsix");
+        pager.height = 2;
+
+        pager.scroll_by(10);
+        assert_eq!(pager.top, 4);
+        pager.scroll_by(-3);
+        assert_eq!(pager.top, 1);
+
+        assert!(pager.search("TWO".to_string()));
+        assert_eq!(pager.found, Some(1));
+        assert!(pager.find_next());
+        assert_eq!(pager.found, Some(4));
+        // the match is on the last screen, which can't be scrolled past
+        assert_eq!(pager.top, 4);
+        assert!(!pager.find_next());
+        assert!(pager.find_previous());
+        assert_eq!(pager.found, Some(1));
+
+        assert!(!pager.search("seven".to_string()));
+        assert!(pager.search("tw".to_string()));
+        assert_eq!(pager.render_line(4, 8), format!("{}enty-t", "Tw".reverse()));
+    }
+}
@@ -0,0 +1,221 @@
+// colours the input by what each part of it is (numbers, functions, etc.),
+// and shows which brackets go together
+
+use std::{collections::HashMap, env, str::FromStr};
+
+use crossterm::style::{Attribute, Color, ContentStyle};
+
+use crate::{
+    input_parsing::{
+        erasable::ErasableType,
+        erasable_cluster::{CursorPositionUnit, ErasableCluster},
+        locale::Locale,
+    },
+    shared::errors::ParsingError,
+};
+
+/// The colours the input is shown in.
+pub struct Theme {
+    colours: HashMap<ErasableType, Color>,
+    // the bracket at the cursor and the one it goes with
+    matching_bracket: Option<Color>,
+    unmatched_bracket: Option<Color>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let colours = [
+            (ErasableType::Digit, Color::Cyan),
+            (ErasableType::BasePrefix, Color::Cyan),
+            (ErasableType::HexDigit, Color::Cyan),
+            (ErasableType::DecimalPoint, Color::Cyan),
+            (ErasableType::ScientificNotation, Color::Cyan),
+            (ErasableType::NamedConstant, Color::Magenta),
+            (ErasableType::FunctionName, Color::Green),
+            (ErasableType::AngleUnit, Color::Yellow),
+            (ErasableType::Unit, Color::Yellow),
+            (ErasableType::Variable, Color::Blue),
+        ];
+
+        Self {
+            colours: HashMap::from(colours),
+            matching_bracket: Some(Color::Yellow),
+            unmatched_bracket: Some(Color::Red),
+        }
+    }
+}
+
+impl Theme {
+    /// No colours at all (brackets are still highlighted in bold).
+    pub fn none() -> Self {
+        Self {
+            colours: HashMap::new(),
+            matching_bracket: None,
+            unmatched_bracket: None,
+        }
+    }
+
+    /// The colours, changed by the `CALCULATOR_COLORS` environment variable
+    /// (eg: `function_name=dark_green,variable=none`), or no colours at all if
+    /// `NO_COLOR` is set.
+    pub fn with_env(self) -> Result<Self, ParsingError> {
+        if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return Ok(Self::none());
+        }
+
+        match env::var("CALCULATOR_COLORS") {
+            Ok(colours) => self.with_colours(&colours),
+            Err(_) => Ok(self),
+        }
+    }
+
+    /// Changes colours given as a comma separated list of `name=colour`,
+    /// where the name is a kind of erasable (eg: digit), matching_bracket or
+    /// unmatched_bracket, and the colour is a name like `red` or `none`.
+    pub fn with_colours(mut self, colours: &str) -> Result<Self, ParsingError> {
+        for setting in colours.split(',').filter(|s| !s.trim().is_empty()) {
+            let Some((name, colour)) = setting.split_once('=') else {
+                return Err(ParsingError::ExpectedButFound {
+                    expected: "name=colour".to_string(),
+                    found: setting.to_string(),
+                });
+            };
+
+            self.set_colour(name.trim(), colour.trim())?;
+        }
+
+        Ok(self)
+    }
+
+    /// Sets the colour of a kind of erasable (eg: digit), or of
+    /// matching_bracket or unmatched_bracket. A colour of `none` removes it.
+    pub fn set_colour(&mut self, name: &str, colour: &str) -> Result<(), ParsingError> {
+        let colour = match colour {
+            "none" => None,
+            colour => {
+                Some(
+                    Color::try_from(colour).map_err(|_| ParsingError::ExpectedButFound {
+                        expected: "a colour (eg: dark_green)".to_string(),
+                        found: colour.to_string(),
+                    })?,
+                )
+            }
+        };
+
+        match name {
+            "matching_bracket" => self.matching_bracket = colour,
+            "unmatched_bracket" => self.unmatched_bracket = colour,
+            name => {
+                let erasable_type =
+                    ErasableType::from_str(name).map_err(|_| ParsingError::ExpectedButFound {
+                        expected: "something to colour (eg: function_name)".to_string(),
+                        found: name.to_string(),
+                    })?;
+
+                match colour {
+                    Some(colour) => self.colours.insert(erasable_type, colour),
+                    None => self.colours.remove(&erasable_type),
+                };
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The input as it's shown: coloured by the theme, with the bracket at the
+/// cursor and the one it goes with highlighted, unmatched brackets marked and
+/// any selection in reverse video. Separators are shown as the locale's.
+pub fn highlight(cluster: &ErasableCluster, theme: &Theme, locale: Locale) -> String {
+    let cursor = cluster.get_cursor_position(CursorPositionUnit::ErasableCount);
+    let unmatched_brackets = cluster.unmatched_brackets();
+    let selection = cluster.selection().unwrap_or_default();
+
+    // the bracket just before the cursor, or otherwise just after it
+    let bracket_pair = [cursor.checked_sub(1), Some(cursor)]
+        .into_iter()
+        .flatten()
+        .find_map(|index| Some((index, cluster.matching_bracket(index)?)));
+
+    let mut output = String::new();
+
+    for (index, e) in cluster.iter().enumerate() {
+        let mut style = ContentStyle::new();
+        style.foreground_color = theme.colours.get(&ErasableType::from(e)).copied();
+
+        if unmatched_brackets.contains(&index) {
+            style.foreground_color = theme.unmatched_bracket;
+        }
+
+        if bracket_pair.is_some_and(|(bracket, other)| index == bracket || index == other) {
+            style.foreground_color = theme.matching_bracket;
+            style.attributes.set(Attribute::Bold);
+        }
+
+        if selection.contains(&index) {
+            style.attributes.set(Attribute::Reverse);
+        }
+
+        output.push_str(&style.apply(locale.text_of(e)).to_string());
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input_parsing::erasable::Erasable;
+
+    #[test]
+    fn colours_can_be_configured() {
+        let theme = Theme::default()
+            .with_colours("digit=red, variable=none,unmatched_bracket=dark_grey")
+            .unwrap();
+
+        assert_eq!(theme.colours.get(&ErasableType::Digit), Some(&Color::Red));
+        assert_eq!(theme.colours.get(&ErasableType::Variable), None);
+        assert_eq!(theme.unmatched_bracket, Some(Color::DarkGrey));
+
+        assert_eq!(
+            Theme::default()
+                .with_colours("digits=red")
+                .err()
+                .unwrap()
+                .to_string(),
+            "error: expected something to colour (eg: function_name) but found digits"
+        );
+        assert!(Theme::default().with_colours("digit=reddish").is_err());
+        assert!(Theme::default().with_colours("digit").is_err());
+    }
+
+    #[test]
+    fn input_without_colours_or_brackets_is_shown_as_it_is() {
+        let cluster = ErasableCluster::build("sin(30deg) + 2x").unwrap();
+        assert_eq!(
+            highlight(&cluster, &Theme::none(), Locale::default()),
+            cluster.to_string()
+        );
+
+        let highlighted = highlight(&cluster, &Theme::default(), Locale::default());
+        assert_ne!(highlighted, cluster.to_string());
+        assert!(highlighted.contains(&format!(
+            "{}",
+            ContentStyle {
+                foreground_color: Some(Color::Green),
+                ..ContentStyle::new()
+            }
+            .apply(<&str>::from(&Erasable::Sin))
+        )));
+    }
+
+    #[test]
+    fn separators_are_shown_as_the_locales() {
+        let cluster = ErasableCluster::build("NthRoot(2, 1.5) + 1").unwrap();
+
+        assert_eq!(
+            highlight(&cluster, &Theme::none(), Locale::DecimalComma),
+            "NthRoot(2; 1,5) + 1"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+// contains the history of entered expressions, which can be moved through with
+// the Up/Down keys and searched with Ctrl-R, and is kept between sessions
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+// the oldest entries are dropped past this
+const HISTORY_LIMIT: usize = 1000;
+
+pub struct InputHistory {
+    // oldest first
+    entries: Vec<String>,
+    // the entry being shown while moving through the history
+    position: Option<usize>,
+    // what was being typed before moving into the history
+    draft: String,
+    // None if the history isn't saved
+    file: Option<PathBuf>,
+}
+
+impl Default for InputHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputHistory {
+    /// Creates an empty history that isn't saved anywhere.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            position: None,
+            draft: String::new(),
+            file: None,
+        }
+    }
+
+    /// The file the history is kept in between sessions, inside the user's
+    /// data directory (eg: ~/.local/share/calculator/history.txt on Linux).
+    pub fn default_file() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("calculator").join("history.txt"))
+    }
+
+    /// Loads the history from `file` (one entry per line), which is also
+    /// where new entries are saved. A missing file gives an empty history.
+    pub fn load(file: &Path) -> io::Result<Self> {
+        let entries = match fs::read_to_string(file) {
+            Ok(contents) => contents.lines().map(|line| line.to_string()).collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        let mut history = Self {
+            entries,
+            file: Some(file.to_path_buf()),
+            ..Self::new()
+        };
+
+        history.enforce_limit();
+
+        Ok(history)
+    }
+
+    fn enforce_limit(&mut self) {
+        if self.entries.len() > HISTORY_LIMIT {
+            self.entries.drain(..self.entries.len() - HISTORY_LIMIT);
+        }
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut contents = self.entries.join("\n");
+        contents.push('\n');
+
+        fs::write(file, contents)
+    }
+
+    /// Adds an entered expression to the end of the history (unless it
+    /// repeats the previous one) and saves it.
+    pub fn push(&mut self, entry: String) -> io::Result<()> {
+        self.position = None;
+        self.draft.clear();
+
+        if entry.trim().is_empty() || self.entries.last() == Some(&entry) {
+            return Ok(());
+        }
+
+        self.entries.push(entry);
+        self.enforce_limit();
+
+        self.save()
+    }
+
+    /// Moves to the entry before the one shown (Up), remembering `current`
+    /// as what was being typed if this is the first move.
+    pub fn older(&mut self, current: &str) -> Option<&str> {
+        let position = match self.position {
+            None => {
+                self.draft = current.to_string();
+                self.entries.len().checked_sub(1)?
+            }
+            Some(position) => position.checked_sub(1)?,
+        };
+
+        self.position = Some(position);
+        Some(&self.entries[position])
+    }
+
+    /// Moves to the entry after the one shown (Down), and back to what was
+    /// being typed after the last one.
+    pub fn newer(&mut self) -> Option<&str> {
+        let position = self.position? + 1;
+
+        if position < self.entries.len() {
+            self.position = Some(position);
+            Some(&self.entries[position])
+        } else {
+            self.position = None;
+            Some(&self.draft)
+        }
+    }
+
+    /// Finds the latest entry containing `query`, only looking before the
+    /// entry at `before` if given (so that searching again finds older ones).
+    pub fn search(&self, query: &str, before: Option<usize>) -> Option<(usize, &str)> {
+        let end = before.unwrap_or(self.entries.len());
+
+        self.entries[..end]
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, entry)| entry.contains(query))
+            .map(|(index, entry)| (index, entry.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(entries: &[&str]) -> InputHistory {
+        let mut history = InputHistory::new();
+
+        for entry in entries {
+            history.push(entry.to_string()).unwrap();
+        }
+
+        history
+    }
+
+    #[test]
+    fn moving_through_the_history_works() {
+        let mut history = history(&["1 + 1", "sin(30deg)", "x = 2"]);
+
+        assert_eq!(history.older("2x"), Some("x = 2"));
+        assert_eq!(history.older("2x"), Some("sin(30deg)"));
+        assert_eq!(history.older("2x"), Some("1 + 1"));
+        assert_eq!(history.older("2x"), None);
+
+        assert_eq!(history.newer(), Some("sin(30deg)"));
+        assert_eq!(history.newer(), Some("x = 2"));
+        // back to what was being typed
+        assert_eq!(history.newer(), Some("2x"));
+        assert_eq!(history.newer(), None);
+    }
+
+    #[test]
+    fn repeated_and_blank_entries_are_skipped() {
+        let history = history(&["1", "1", " ", "2", "1"]);
+        assert_eq!(history.entries, ["1", "2", "1"]);
+    }
+
+    #[test]
+    fn searching_finds_the_latest_match_first() {
+        let history = history(&["sin(30deg)", "2 + 2", "sin(45deg)"]);
+
+        assert_eq!(history.search("sin", None), Some((2, "sin(45deg)")));
+        assert_eq!(history.search("sin", Some(2)), Some((0, "sin(30deg)")));
+        assert_eq!(history.search("sin", Some(0)), None);
+        assert_eq!(history.search("cos", None), None);
+    }
+
+    #[test]
+    fn the_history_is_saved_and_limited() {
+        let file = std::env::temp_dir()
+            .join(format!("calculator-test-{}", std::process::id()))
+            .join("history.txt");
+
+        let mut history = InputHistory::load(&file).unwrap();
+        for i in 0..HISTORY_LIMIT + 5 {
+            history.push(i.to_string()).unwrap();
+        }
+
+        let loaded = InputHistory::load(&file).unwrap();
+        assert_eq!(loaded.entries.len(), HISTORY_LIMIT);
+        assert_eq!(loaded.entries.first().unwrap(), "5");
+        assert_eq!(
+            loaded.entries.last().unwrap(),
+            &(HISTORY_LIMIT + 4).to_string()
+        );
+
+        fs::remove_dir_all(file.parent().unwrap()).unwrap();
+    }
+}
@@ -2,12 +2,14 @@ use std::fmt::Display;
 
 use num_traits::FromPrimitive;
 use striminant_macro::striminant;
-use strum_macros::{EnumIter, IntoStaticStr};
+use strum_macros::{EnumIter, EnumString, IntoStaticStr};
 
 use crate::shared::errors::ParsingError;
 
 #[repr(u8)]
-#[striminant(except = [b'h', b'q'])]
+// every action's default key (see KeyBindings), which can't type anything:
+// help, quit, the angle unit, the word size and the notation
+#[striminant(except = [b'h', b'q', b'U', b'@', b'#'])]
 #[derive(Debug, PartialEq, EnumIter, FromPrimitive, ToPrimitive, IntoStaticStr, Clone)]
 pub enum Erasable {
     // digits
@@ -21,6 +23,26 @@ pub enum Erasable {
     Seven = b'7',
     Eight = b'8',
     Nine = b'9',
+    // what follows the 0 of an integer in another base (eg: 0x1F, 0b1011 and
+    // 0o17), and the digits above 9; only read as such in those integers
+    #[strum(serialize = "x")]
+    HexadecimalPrefix = 170,
+    #[strum(serialize = "b")]
+    BinaryPrefix = 171,
+    #[strum(serialize = "o")]
+    OctalPrefix = 172,
+    #[strum(serialize = "A")]
+    HexDigitA = 173,
+    #[strum(serialize = "B")]
+    HexDigitB = 174,
+    #[strum(serialize = "C")]
+    HexDigitC = 175,
+    #[strum(serialize = "D")]
+    HexDigitD = 176,
+    #[strum(serialize = "E")]
+    HexDigitE = 177,
+    #[strum(serialize = "F")]
+    HexDigitF = 178,
 
     // arithmetic operators
     PlusSign = b'+',
@@ -52,6 +74,9 @@ pub enum Erasable {
     #[strum(serialize = "pi")]
     Pi = b'p',
     E = b'e',
+    // the previous result; eg: ans, or ans2 for the second one
+    #[strum(serialize = "ans")]
+    Answer = b'A',
 
     // functions
     #[strum(serialize = "abs")]
@@ -80,6 +105,129 @@ pub enum Erasable {
     Degrees = b'd',
     #[strum(serialize = "rad")]
     Radians = b'r',
+    #[strum(serialize = "grad")]
+    Gradians = b'G',
+    #[strum(serialize = "turn")]
+    Turns = b'O',
+    // degrees, minutes and seconds; eg: 12°30'15"
+    #[strum(serialize = "°")]
+    DegreeSign = b'D',
+    ArcMinutes = b'\'',
+    ArcSeconds = b'"',
+
+    // other units, which have no key, so are only typed by name
+    #[strum(serialize = "m")]
+    Metre = 128,
+    #[strum(serialize = "g")]
+    Gram = 129,
+    #[strum(serialize = "s")]
+    Second = 130,
+    #[strum(serialize = "A")]
+    Ampere = 131,
+    #[strum(serialize = "K")]
+    Kelvin = 132,
+    #[strum(serialize = "mol")]
+    Mole = 133,
+    #[strum(serialize = "min")]
+    Minute = 134,
+    #[strum(serialize = "h")]
+    Hour = 135,
+    #[strum(serialize = "L")]
+    Litre = 136,
+    #[strum(serialize = "Hz")]
+    Hertz = 137,
+    #[strum(serialize = "N")]
+    Newton = 138,
+    #[strum(serialize = "J")]
+    Joule = 139,
+    #[strum(serialize = "W")]
+    Watt = 140,
+    #[strum(serialize = "Pa")]
+    Pascal = 141,
+    #[strum(serialize = "V")]
+    Volt = 142,
+    #[strum(serialize = "°C")]
+    Celsius = 143,
+    #[strum(serialize = "°F")]
+    Fahrenheit = 144,
+    #[strum(serialize = "mi")]
+    Mile = 145,
+    #[strum(serialize = "yd")]
+    Yard = 146,
+    #[strum(serialize = "ft")]
+    Foot = 147,
+    #[strum(serialize = "inch")]
+    Inch = 148,
+    #[strum(serialize = "lb")]
+    Pound = 149,
+    #[strum(serialize = "oz")]
+    Ounce = 150,
+    #[strum(serialize = "day")]
+    Day = 151,
+    #[strum(serialize = "gal")]
+    Gallon = 152,
+    #[strum(serialize = "cal")]
+    Calorie = 153,
+    #[strum(serialize = "C")]
+    Coulomb = 154,
+    #[strum(serialize = "F")]
+    Farad = 155,
+
+    // prefixes, eg: the k of km
+    #[strum(serialize = "n")]
+    Nano = 160,
+    #[strum(serialize = "µ")]
+    Micro = 161,
+    #[strum(serialize = "m")]
+    Milli = 162,
+    #[strum(serialize = "c")]
+    Centi = 163,
+    #[strum(serialize = "k")]
+    Kilo = 164,
+    #[strum(serialize = "M")]
+    Mega = 165,
+    #[strum(serialize = "G")]
+    Giga = 166,
+
+    // bitwise operators, which work with whole numbers; eg: 6 and 3
+    #[strum(serialize = "and")]
+    BitwiseAnd = b'&',
+    #[strum(serialize = "or")]
+    BitwiseOr = b'|',
+    #[strum(serialize = "xor")]
+    BitwiseXor = b'X',
+    #[strum(serialize = "not")]
+    BitwiseNot = b'~',
+    #[strum(serialize = "<<")]
+    ShiftLeft = b'<',
+    // typed as > twice, as > is ->
+    #[strum(serialize = ">>")]
+    ShiftRight = 179,
+
+    // converts a result into other units; eg: 5 km/h -> m/s
+    #[strum(serialize = "->")]
+    ConversionArrow = b'>',
+
+    // variables (the lowercase letters not taken by anything else)
+    VariableB = b'b',
+    VariableF = b'f',
+    VariableG = b'g',
+    VariableI = b'i',
+    VariableJ = b'j',
+    VariableK = b'k',
+    VariableL = b'l',
+    VariableM = b'm',
+    VariableN = b'n',
+    VariableO = b'o',
+    VariableU = b'u',
+    VariableV = b'v',
+    VariableW = b'w',
+    VariableX = b'x',
+    VariableY = b'y',
+    VariableZ = b'z',
+
+    // assignment; eg: x = 2
+    EqualsSign = b'=',
 }
 impl Display for Erasable {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -88,9 +236,15 @@ impl Display for Erasable {
     }
 }
 
-#[derive(PartialEq, Eq, Debug)]
+// named in snake case (eg: function_name) where colours are configured
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, EnumString)]
+#[strum(serialize_all = "snake_case")]
 pub enum ErasableType {
     Digit,
+    // eg: the x of 0x1F
+    BasePrefix,
+    // A to F
+    HexDigit,
     ArithmeticOperator,
     OpeningBracket,
     ClosingBracket,
@@ -103,6 +257,12 @@ pub enum ErasableType {
     FractionDivider,
     ExponentPlaceholder,
     AngleUnit,
+    // units other than angles, and their prefixes
+    Unit,
+    ConversionOperator,
+    BitwiseOperator,
+    Variable,
+    AssignmentOperator,
 }
 
 impl From<&Erasable> for ErasableType {
@@ -113,6 +273,10 @@ impl From<&Erasable> for ErasableType {
             Zero | One | Two | Three | Four | Five | Six | Seven | Eight | Nine => {
                 ErasableType::Digit
             }
+            HexadecimalPrefix | BinaryPrefix | OctalPrefix => ErasableType::BasePrefix,
+            HexDigitA | HexDigitB | HexDigitC | HexDigitD | HexDigitE | HexDigitF => {
+                ErasableType::HexDigit
+            }
             PlusSign | NegativeSign | MultiplicationSign | DivisionSign => {
                 ErasableType::ArithmeticOperator
             }
@@ -120,30 +284,160 @@ impl From<&Erasable> for ErasableType {
             RightCurly | RightParenthesis | RightSquare => ErasableType::ClosingBracket,
             Space => ErasableType::Formatting,
             DecimalPoint => ErasableType::DecimalPoint,
-            Pi | E => ErasableType::NamedConstant,
+            Pi | E | Answer => ErasableType::NamedConstant,
             Absolute | Sin | Cos | Tan | Arcsin | Arccos | Arctan | NthRoot => {
                 ErasableType::FunctionName
             }
             FractionDivider => ErasableType::FractionDivider,
-            Degrees | Radians => ErasableType::AngleUnit,
+            Degrees | Radians | Gradians | Turns | DegreeSign | ArcMinutes | ArcSeconds => {
+                ErasableType::AngleUnit
+            }
+            Metre | Gram | Second | Ampere | Kelvin | Mole | Minute | Hour | Litre | Hertz
+            | Newton | Joule | Watt | Pascal | Volt | Celsius | Fahrenheit | Mile | Yard | Foot
+            | Inch | Pound | Ounce | Day | Gallon | Calorie | Coulomb | Farad | Nano | Micro
+            | Milli | Centi | Kilo | Mega | Giga => ErasableType::Unit,
+            ConversionArrow => ErasableType::ConversionOperator,
+            BitwiseAnd | BitwiseOr | BitwiseXor | BitwiseNot | ShiftLeft | ShiftRight => {
+                ErasableType::BitwiseOperator
+            }
             TimesTenToThePowerOf => ErasableType::ScientificNotation,
             Comma => ErasableType::Comma,
             ExponentPlaceholder => ErasableType::ExponentPlaceholder,
+            VariableB | VariableF | VariableG | VariableI | VariableJ | VariableK | VariableL
+            | VariableM | VariableN | VariableO | VariableU | VariableV | VariableW | VariableX
+            | VariableY | VariableZ => ErasableType::Variable,
+            EqualsSign => ErasableType::AssignmentOperator,
         }
     }
 }
 
 impl Erasable {
     pub fn build(c: char) -> Result<Self, ParsingError> {
+        // otherwise `c as u8` would truncate it into some other character
+        if !c.is_ascii() {
+            return Err(ParsingError::NoSuchCharacterCode);
+        }
+
         match <Erasable as FromPrimitive>::from_u8(c as u8) {
             Some(e) => Ok(e),
             None => Err(ParsingError::NoSuchCharacterCode),
         }
     }
 
+    /// The bracket that closes this one, or opens it if it's a closing bracket.
+    pub fn matching_bracket(&self) -> Option<Erasable> {
+        use Erasable::*;
+
+        match self {
+            LeftParenthesis => Some(RightParenthesis),
+            RightParenthesis => Some(LeftParenthesis),
+            LeftCurly => Some(RightCurly),
+            RightCurly => Some(LeftCurly),
+            LeftSquare => Some(RightSquare),
+            RightSquare => Some(LeftSquare),
+            _ => None,
+        }
+    }
+
+    /// What the erasable is, as listed in the help. None for the ones that
+    /// need no introduction (eg: digits) or are covered by the help's text
+    /// (eg: brackets and variables).
+    pub fn description(&self) -> Option<&'static str> {
+        use Erasable::*;
+
+        let description = match self {
+            PlusSign => "addition",
+            NegativeSign => "subtraction, or a negative number",
+            MultiplicationSign => "multiplication",
+            DivisionSign => "division",
+            FractionDivider => "a fraction, eg: 1_2 is 1/2",
+            ExponentPlaceholder => "to the power of, eg: (2)^(10)",
+            Comma => "separates a function's arguments",
+            Pi => "pi",
+            E => "Euler's number",
+            Answer => "the previous result, or an earlier one, eg: ans1",
+            Absolute => "absolute value: abs(value)",
+            Sin => "sine: sin(angle)",
+            Cos => "cosine: cos(angle)",
+            Tan => "tangent: tan(angle)",
+            Arcsin => "arcsine: asin(value)",
+            Arccos => "arccosine: acos(value)",
+            Arctan => "arctangent: atan(value)",
+            NthRoot => "nth root: NthRoot(degree of root, value under root)",
+            Degrees => "degrees, eg: 30deg",
+            Radians => "radians, eg: 2rad",
+            Gradians => "gradians, eg: 50grad",
+            Turns => "turns, eg: 0.25turn",
+            DegreeSign => "degrees, with minutes and seconds, eg: 12°30'15\"",
+            ArcMinutes => "minutes of a degree, eg: 30'",
+            ArcSeconds => "seconds of a degree, eg: 15\"",
+            Metre => "metres",
+            Gram => "grams",
+            Second => "seconds",
+            Ampere => "amperes",
+            Kelvin => "kelvins",
+            Mole => "moles",
+            Minute => "minutes",
+            Hour => "hours",
+            Litre => "litres",
+            Hertz => "hertz",
+            Newton => "newtons",
+            Joule => "joules",
+            Watt => "watts",
+            Pascal => "pascals",
+            Volt => "volts",
+            Celsius => "degrees Celsius (or degC), eg: 100 °F -> °C",
+            Fahrenheit => "degrees Fahrenheit (or degF)",
+            Mile => "miles",
+            Yard => "yards",
+            Foot => "feet",
+            Inch => "inches",
+            Pound => "pounds",
+            Ounce => "ounces",
+            Day => "days",
+            Gallon => "US gallons",
+            Calorie => "calories, eg: 2000 kcal",
+            Coulomb => "coulombs",
+            Farad => "farads, eg: 10 µF",
+            Nano => "nano, a billionth",
+            Micro => "micro, a millionth",
+            Milli => "milli, a thousandth",
+            Centi => "centi, a hundredth",
+            Kilo => "kilo, a thousand",
+            Mega => "mega, a million",
+            Giga => "giga, a billion",
+            ConversionArrow => "converts into other units, eg: 5 km/h -> m/s (or 5 km/h in m/s)",
+            BitwiseAnd => "bitwise and, eg: 6 and 3 = 2",
+            BitwiseOr => "bitwise or, eg: 6 or 3 = 7",
+            BitwiseXor => "bitwise exclusive or, eg: 6 xor 3 = 5",
+            BitwiseNot => "bitwise not, eg: not 0 = -1",
+            ShiftLeft => "shifts the bits left, eg: 1 << 4 = 16",
+            ShiftRight => "shifts the bits right (press > twice), eg: 16 >> 2 = 4",
+            EqualsSign => "assigns a variable or defines a function, eg: x = 2",
+            _ => return None,
+        };
+
+        Some(description)
+    }
+
+    /// What follows a function's name: its opening bracket, then the commas
+    /// between its arguments and its closing bracket. None if this isn't a
+    /// function's name.
+    pub fn argument_template(&self) -> Option<(Erasable, Vec<Erasable>)> {
+        use Erasable::*;
+
+        match self {
+            NthRoot => Some((LeftParenthesis, vec![Comma, RightParenthesis])),
+            Absolute | Sin | Cos | Tan | Arcsin | Arccos | Arctan => {
+                Some((LeftParenthesis, vec![RightParenthesis]))
+            }
+            _ => None,
+        }
+    }
+
     pub fn length_in_chars(&self) -> usize {
         let str: &'static str = self.into();
-        str.len()
+        str.chars().count()
     }
 }
 
@@ -162,4 +456,10 @@ mod tests {
     fn invalid_characters_panic() {
         Erasable::build('h').unwrap();
     }
+
+    #[test]
+    fn non_ascii_characters_are_rejected() {
+        // 'ŧ' as u8 would be b'g'
+        assert!(Erasable::build('ŧ').is_err());
+    }
 }
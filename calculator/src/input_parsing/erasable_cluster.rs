@@ -1,6 +1,10 @@
-use std::slice::Iter;
+use std::{ops::Range, slice::Iter};
 
-use super::erasable::Erasable;
+use super::{
+    erasable::{Erasable, ErasableType},
+    locale::Locale,
+    tokenizer::{is_start_of_name, is_start_of_unit_after, spell, tokenize, tokenize_after},
+};
 use crate::{
     display::DisplayUnit,
     shared::{
@@ -13,8 +17,43 @@ pub struct ErasableCluster {
     erasables: Vec<Erasable>,
     cursor: Cursor,
     display_cache: Vec<DisplayUnit>,
+    word: Option<Word>,
+    // states to go back to (undo) or forward to again (redo), latest last
+    undo_stack: Vec<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+    last_edit: Option<Edit>,
+    // where a selection was started (as an erasable count, like the cursor);
+    // the selection is everything between here and the cursor
+    selection_anchor: Option<usize>,
+}
+
+// letters typed one after another at the cursor, so that typing `sin` gives
+// sin rather than sin, i and n
+#[derive(Default)]
+struct Word {
+    text: String,
+    // how many erasables (just before the cursor) the text was tokenized into
+    length: usize,
+    // how many erasables just after the cursor were put in to finish off a
+    // function, eg: the comma and closing bracket of NthRoot(,)
+    template_length: usize,
+}
+
+// the state of the cluster before an edit
+struct Snapshot {
+    erasables: Vec<Erasable>,
+    cursor: CursorPosition,
+}
+
+// the kind of the last edit, so that typing a number is undone all at once
+// rather than a digit at a time
+#[derive(PartialEq)]
+enum Edit {
+    NumberInput,
+    Other,
 }
 
+#[derive(Clone)]
 enum CursorPosition {
     Empty,
     NotEmpty(usize),
@@ -64,6 +103,12 @@ pub enum CursorPositionUnit {
     DisplaySegmentChars,
 }
 
+impl Default for ErasableCluster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// IMPORTANT: The cursor's position is after the element it refers to.
 impl ErasableCluster {
     /// Initializes an ErasableCluster with defaults: The vector of erasables is
@@ -74,6 +119,11 @@ impl ErasableCluster {
             erasables: Vec::new(),
             cursor: Cursor::new(),
             display_cache: Vec::new(),
+            word: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            last_edit: None,
+            selection_anchor: None,
         }
     }
 
@@ -110,7 +160,7 @@ impl ErasableCluster {
 
         for e in self.erasables.iter() {
             if e == &Erasable::FractionDivider {
-                let bracket_depth = 0;
+                let _bracket_depth = 0;
                 // let start
             } else {
                 content.push_str(e.into());
@@ -121,9 +171,9 @@ impl ErasableCluster {
         //
     }
 
-    /// Builds a new cluster from the string input. Each string character
-    /// corresponds to an erasable. Each erasable has a char code, as
-    /// in the Erasable::build function.
+    /// Builds a new cluster from the string input. Characters are read as
+    /// in the tokenize function: either as the full name of an erasable
+    /// (eg: sin) or as its char code (eg: s), as in the Erasable::build function.
     ///
     /// The cursor is automatically moved to the next element to add in
     /// the vector of erasables (but the index is never out of bounds).
@@ -133,66 +183,57 @@ impl ErasableCluster {
             return Err(ParsingError::CannotParseEmptyString);
         }
 
-        let mut position_in_chars: usize = 0;
-        let erasables: Result<Vec<Erasable>, ParsingError> = s
-            .chars()
-            .map(|c| {
-                let erasable = Erasable::build(c);
+        let erasables = tokenize(s)?;
 
-                if let Ok(erasable) = erasable {
-                    position_in_chars += erasable.length_in_chars();
-                    Ok(erasable)
-                } else {
-                    erasable
-                }
-            })
-            .collect();
+        let cursor = Cursor {
+            position: CursorPosition::NotEmpty(erasables.len() - 1),
+        };
 
-        match erasables {
-            Ok(erasables) => {
-                let cursor = Cursor {
-                    position: CursorPosition::NotEmpty(erasables.len() - 1),
-                };
+        let mut result = Self {
+            erasables,
+            cursor,
+            display_cache: Vec::new(),
+            word: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            last_edit: None,
+            selection_anchor: None,
+        };
 
-                let mut result = Self {
-                    erasables,
-                    cursor,
-                    display_cache: Vec::new(),
-                };
+        result.refresh_display_cache();
 
-                result.refresh_display_cache();
+        Ok(result)
+    }
 
-                Ok(result)
-            }
-            Err(e) => Err(e),
-        }
-    }
-
-    // /// Gets the current position of the cursor, in terms of the number of
-    // /// characters in the expression or the number of erasables in the expression.
-    // ///
-    // /// The cursor position will have a value ranging from 0 to the number of
-    // /// characters/erasables. This means it either points to an element in the
-    // /// list of erasables or to the last item.
-    // ///
-    // /// Upon building a new ErasableCluster, the cursor position is automatically
-    // /// moved to the last element.
-    // ///
-    // /// Note: This returns None also when the cursor is at the start, even if there are
-    // /// erasables.
-    // ///
-    // pub fn get_cursor_position(&self, unit: CursorPositionUnit) -> Option<usize> {
-    //     match &(self.cursor.position) {
-    //         CursorPosition::Empty | CursorPosition::Start => None,
-    //         CursorPosition::NotEmpty(position) => match unit {
-    //             CursorPositionUnit::ErasableCount => Some(*position),
-    //             _ => todo!(),
-    //         },
-    //     }
-    // }
+    /// Gets the current position of the cursor, in terms of the number of
+    /// characters or the number of erasables before it.
+    ///
+    /// Upon building a new ErasableCluster, the cursor position is automatically
+    /// moved to the end.
+    ///
+    pub fn get_cursor_position(&self, unit: CursorPositionUnit) -> usize {
+        let erasable_count = match &(self.cursor.position) {
+            CursorPosition::Empty | CursorPosition::Start => 0,
+            CursorPosition::NotEmpty(position) => (position + 1).min(self.erasables.len()),
+        };
+
+        match unit {
+            CursorPositionUnit::ErasableCount => erasable_count,
+            // the display module isn't hooked up yet, so segments are plain text
+            CursorPositionUnit::Chars | CursorPositionUnit::DisplaySegmentChars => self.erasables
+                [..erasable_count]
+                .iter()
+                .map(|e| e.length_in_chars())
+                .sum(),
+        }
+    }
 
     /// Attempts to move the cursor to the next erasable.
     pub fn move_cursor_to_next_erasable(&mut self) -> Option<&Erasable> {
+        self.word = None;
+        self.last_edit = None;
+        self.selection_anchor = None;
+
         if self.is_cursor_at_end() {
             return None;
         }
@@ -207,7 +248,7 @@ impl ErasableCluster {
                 e
             }
             CursorPosition::Start => {
-                let e = self.erasables.get(0);
+                let e = self.erasables.first();
                 self.cursor.move_toward(Sign::Positive);
 
                 e
@@ -218,6 +259,10 @@ impl ErasableCluster {
     /// Attempts to move the cursor to the previous erasable, and returns the erasable
     /// that has been moved from (if successful).
     pub fn move_cursor_to_prev_erasable(&mut self) -> Option<&Erasable> {
+        self.word = None;
+        self.last_edit = None;
+        self.selection_anchor = None;
+
         match &(self.cursor.position) {
             CursorPosition::Empty | CursorPosition::Start => None,
             CursorPosition::NotEmpty(position) => {
@@ -232,9 +277,221 @@ impl ErasableCluster {
         }
     }
 
+    // puts the cursor after the first `erasable_count` erasables
+    fn move_cursor_to(&mut self, erasable_count: usize) {
+        self.word = None;
+        self.last_edit = None;
+        self.selection_anchor = None;
+
+        self.cursor.position = if self.erasables.is_empty() {
+            CursorPosition::Empty
+        } else if erasable_count == 0 {
+            CursorPosition::Start
+        } else {
+            CursorPosition::NotEmpty(erasable_count.min(self.erasables.len()) - 1)
+        };
+    }
+
+    /// Moves the cursor before the first erasable (Home).
+    pub fn move_cursor_to_start(&mut self) {
+        self.move_cursor_to(0);
+    }
+
+    /// Moves the cursor after the last erasable (End).
+    pub fn move_cursor_to_end(&mut self) {
+        self.move_cursor_to(self.erasables.len());
+    }
+
+    // moves the cursor, selecting everything it passes over
+    fn select_to(&mut self, erasable_count: usize) {
+        let anchor = self
+            .selection_anchor
+            .unwrap_or_else(|| self.get_cursor_position(CursorPositionUnit::ErasableCount));
+
+        self.move_cursor_to(erasable_count);
+        self.selection_anchor = Some(anchor);
+    }
+
+    /// Extends the selection by one erasable to the left (Shift-Left).
+    pub fn select_prev_erasable(&mut self) {
+        let position = self.get_cursor_position(CursorPositionUnit::ErasableCount);
+        self.select_to(position.saturating_sub(1));
+    }
+
+    /// Extends the selection by one erasable to the right (Shift-Right).
+    pub fn select_next_erasable(&mut self) {
+        let position = self.get_cursor_position(CursorPositionUnit::ErasableCount);
+        self.select_to(position + 1);
+    }
+
+    /// Extends the selection to the start (Shift-Home).
+    pub fn select_to_start(&mut self) {
+        self.select_to(0);
+    }
+
+    /// Extends the selection to the end (Shift-End).
+    pub fn select_to_end(&mut self) {
+        self.select_to(self.erasables.len());
+    }
+
+    /// The selected erasables, as a range of indices. None if nothing is
+    /// selected.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.selection_anchor?;
+        let cursor = self.get_cursor_position(CursorPositionUnit::ErasableCount);
+
+        match anchor.cmp(&cursor) {
+            std::cmp::Ordering::Less => Some(anchor..cursor),
+            std::cmp::Ordering::Greater => Some(cursor..anchor),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// Returns a copy of the selected erasables (for the clipboard).
+    pub fn copy_selection(&self) -> Option<Vec<Erasable>> {
+        Some(self.erasables[self.selection()?].to_vec())
+    }
+
+    /// Removes the selected erasables, returning them (for the clipboard).
+    pub fn cut_selection(&mut self) -> Option<Vec<Erasable>> {
+        let selection = self.selection()?;
+
+        self.record_edit(Edit::Other);
+        Some(self.drain(selection))
+    }
+
+    // removes erasables without recording an edit, leaving the cursor where they were
+    fn drain(&mut self, range: Range<usize>) -> Vec<Erasable> {
+        let start = range.start;
+        let drained = self.erasables.drain(range).collect();
+        self.move_cursor_to(start);
+
+        drained
+    }
+
+    /// Removes the selected erasables, if any. Returns false if nothing was
+    /// selected.
+    pub fn remove_selection(&mut self) -> bool {
+        self.cut_selection().is_some()
+    }
+
+    // wraps the selection in brackets; eg: selecting 1 + 2 and typing ( gives (1 + 2)
+    fn wrap_selection(&mut self, opening_bracket: Erasable, selection: Range<usize>) {
+        let Some(closing_bracket) = opening_bracket.matching_bracket() else {
+            return;
+        };
+
+        self.record_edit(Edit::Other);
+        self.erasables.insert(selection.end, closing_bracket);
+        self.erasables.insert(selection.start, opening_bracket);
+        self.move_cursor_to(selection.end + 2);
+    }
+
+    // pairs up the brackets (of any kind, since they're interchangeable),
+    // returning the pairs and the indices of any brackets left over
+    fn match_brackets(&self) -> (Vec<(usize, usize)>, Vec<usize>) {
+        let mut pairs = vec![];
+        let mut open = vec![];
+        let mut unmatched = vec![];
+
+        for (index, e) in self.erasables.iter().enumerate() {
+            match ErasableType::from(e) {
+                ErasableType::OpeningBracket => open.push(index),
+                ErasableType::ClosingBracket => match open.pop() {
+                    Some(opening) => pairs.push((opening, index)),
+                    None => unmatched.push(index),
+                },
+                _ => {}
+            }
+        }
+
+        unmatched.extend(open);
+        unmatched.sort_unstable();
+
+        (pairs, unmatched)
+    }
+
+    /// The index of the bracket that goes with the one at `index`, if it has one.
+    pub fn matching_bracket(&self, index: usize) -> Option<usize> {
+        self.match_brackets()
+            .0
+            .into_iter()
+            .find_map(|(opening, closing)| match index {
+                _ if index == opening => Some(closing),
+                _ if index == closing => Some(opening),
+                _ => None,
+            })
+    }
+
+    /// The indices of brackets that aren't opened or closed.
+    pub fn unmatched_brackets(&self) -> Vec<usize> {
+        self.match_brackets().1
+    }
+
+    fn is_number_at(&self, index: usize) -> bool {
+        matches!(
+            self.erasables.get(index).map(ErasableType::from),
+            Some(ErasableType::Digit | ErasableType::DecimalPoint)
+        )
+    }
+
+    fn is_type_at(&self, index: usize, erasable_type: ErasableType) -> bool {
+        self.erasables.get(index).map(ErasableType::from) == Some(erasable_type)
+    }
+
+    /// Moves the cursor past the next token (Ctrl-Right): a whole number, a
+    /// function name with its opening bracket, or otherwise one erasable.
+    /// Spaces before it are skipped over too.
+    pub fn move_cursor_to_next_token(&mut self) {
+        let mut index = self.get_cursor_position(CursorPositionUnit::ErasableCount);
+
+        while self.is_type_at(index, ErasableType::Formatting) {
+            index += 1;
+        }
+
+        if self.is_number_at(index) {
+            while self.is_number_at(index) {
+                index += 1;
+            }
+        } else if self.is_type_at(index, ErasableType::FunctionName)
+            && self.is_type_at(index + 1, ErasableType::OpeningBracket)
+        {
+            index += 2;
+        } else {
+            index += 1;
+        }
+
+        self.move_cursor_to(index);
+    }
+
+    /// Moves the cursor back before the previous token (Ctrl-Left), as in
+    /// move_cursor_to_next_token.
+    pub fn move_cursor_to_prev_token(&mut self) {
+        let mut index = self.get_cursor_position(CursorPositionUnit::ErasableCount);
+
+        while index > 0 && self.is_type_at(index - 1, ErasableType::Formatting) {
+            index -= 1;
+        }
+
+        if index > 0 && self.is_number_at(index - 1) {
+            while index > 0 && self.is_number_at(index - 1) {
+                index -= 1;
+            }
+        } else if index > 1
+            && self.is_type_at(index - 1, ErasableType::OpeningBracket)
+            && self.is_type_at(index - 2, ErasableType::FunctionName)
+        {
+            index -= 2;
+        } else {
+            index = index.saturating_sub(1);
+        }
+
+        self.move_cursor_to(index);
+    }
+
     fn is_cursor_at_end(&self) -> bool {
         if let CursorPosition::NotEmpty(position) = &(self.cursor.position) {
-            if self.erasables.len() == 0 {
+            if self.erasables.is_empty() {
                 false
             } else {
                 *position == self.erasables.len() - 1
@@ -248,18 +505,254 @@ impl ErasableCluster {
     /// the element pointed to by the cursor.
     ///
     /// It also updates the cursor to look at the element that has just been added.
+    ///
+    /// If there's a selection, it's replaced by the erasable, except for
+    /// opening brackets which wrap the selection instead.
     pub fn add_at_cursor_position(&mut self, c: char) -> Result<&Erasable, ParsingError> {
         let e = Erasable::build(c)?;
 
+        if let Some(selection) = self.selection() {
+            if ErasableType::from(&e) == ErasableType::OpeningBracket {
+                self.wrap_selection(e, selection.clone());
+                return Ok(&self.erasables[selection.start]);
+            }
+
+            self.remove_selection();
+        }
+
+        let edit = match ErasableType::from(&e) {
+            ErasableType::Digit | ErasableType::DecimalPoint => Edit::NumberInput,
+            _ => Edit::Other,
+        };
+
+        self.record_edit(edit);
+        self.word = None;
+        Ok(self.insert_at_cursor_position(e))
+    }
+
+    /// Like add_at_cursor_position, but letters typed one after another are
+    /// read together, so that full names like `sin` and `deg` can be typed as
+    /// well as their one-letter codes.
+    ///
+    /// Brackets are closed automatically, and functions get their brackets
+    /// (and commas between arguments) too, eg: typing `s` gives `sin()` with
+    /// the cursor inside. Typing a closing bracket or comma that's already
+    /// there steps over it.
+    pub fn type_at_cursor_position(&mut self, c: char) -> Result<(), ParsingError> {
+        if !c.is_ascii_alphabetic() {
+            return self.type_symbol_at_cursor_position(c);
+        }
+
+        // start the word afresh in place of the selection
+        if self.remove_selection() {
+            self.word = None;
+        }
+
+        // once a function's name can't go on any further, letters go inside
+        // its brackets, eg: `sx` gives sin(x)
+        let continues_word = self.word.as_ref().is_some_and(|word| {
+            word.template_length == 0 || is_start_of_name(&format!("{}{c}", word.text))
+        });
+
+        let mut word = match continues_word {
+            true => self.word.take().unwrap_or_default(),
+            false => Word::default(),
+        };
+
+        word.text.push(c);
+
+        // what's before the word decides whether eg: m is metres or a variable
+        let start = self.get_cursor_position(CursorPositionUnit::ErasableCount) - word.length;
+
+        // keys are bound to the standard separators whatever the locale
+        let erasables =
+            match tokenize_after(&word.text, &self.erasables[..start], Locale::default()) {
+                Ok(erasables) => erasables,
+                Err(e) => {
+                    // carry on from the letters before, which were fine
+                    if continues_word {
+                        word.text.pop();
+                        self.word = Some(word);
+                    }
+
+                    return Err(e);
+                }
+            };
+
+        self.replace_word(word, erasables)
+    }
+
+    // puts the erasables (and the rest of a function's template) in place of
+    // the word's, and carries on the word with them
+    fn replace_word(
+        &mut self,
+        mut word: Word,
+        mut erasables: Vec<Erasable>,
+    ) -> Result<(), ParsingError> {
+        self.record_edit(Edit::Other);
+
+        for _ in 0..word.length {
+            self.remove_erasable_at_cursor_position()
+                .map_err(|e| ParsingError::Custom(e.to_string()))?;
+        }
+
+        let index = self.get_cursor_position(CursorPositionUnit::ErasableCount);
+        self.erasables.drain(index..index + word.template_length);
+
+        let template = erasables.last().and_then(Erasable::argument_template);
+        word.template_length = 0;
+
+        if let Some((opening_bracket, rest)) = template {
+            erasables.push(opening_bracket);
+            word.template_length = rest.len();
+
+            for e in rest.into_iter().rev() {
+                self.erasables.insert(index, e);
+            }
+        }
+
+        word.length = erasables.len();
+
+        for e in erasables {
+            self.insert_at_cursor_position(e);
+        }
+
+        self.word = Some(word);
+
+        Ok(())
+    }
+
+    /// Whether typing the letter would carry on the name being typed (eg: the
+    /// q of sqrt), or start a unit after a quantity (eg: the h of 5 h).
+    pub fn continues_word(&self, c: char) -> bool {
+        let before = &self.erasables[..self.get_cursor_position(CursorPositionUnit::ErasableCount)];
+
+        c.is_ascii_alphabetic()
+            && (self
+                .word
+                .as_ref()
+                .is_some_and(|word| is_start_of_name(&format!("{}{c}", word.text)))
+                || is_start_of_unit_after(&c.to_string(), before))
+    }
+
+    // typing anything other than a letter
+    fn type_symbol_at_cursor_position(&mut self, c: char) -> Result<(), ParsingError> {
+        let e = Erasable::build(c)?;
+        let index = self.get_cursor_position(CursorPositionUnit::ErasableCount);
+
+        match ErasableType::from(&e) {
+            ErasableType::OpeningBracket if self.selection().is_none() => {
+                // a function's bracket was already put in with its name
+                if self
+                    .word
+                    .take()
+                    .is_some_and(|word| word.template_length > 0)
+                {
+                    return Ok(());
+                }
+
+                let closing_bracket = e.matching_bracket();
+
+                self.add_at_cursor_position(c)?;
+                if let Some(closing_bracket) = closing_bracket {
+                    self.erasables.insert(index + 1, closing_bracket);
+                }
+
+                Ok(())
+            }
+            ErasableType::ClosingBracket | ErasableType::Comma
+                if self.erasables.get(index) == Some(&e) =>
+            {
+                self.move_cursor_to(index + 1);
+                Ok(())
+            }
+            // -> can be typed as - then >
+            ErasableType::ConversionOperator
+                if index > 0 && self.erasables.get(index - 1) == Some(&Erasable::NegativeSign) =>
+            {
+                self.remove_erasable_at_cursor_position()
+                    .map_err(|e| ParsingError::Custom(e.to_string()))?;
+                self.add_at_cursor_position(c).map(|_| ())
+            }
+            // and >> as > twice
+            ErasableType::ConversionOperator
+                if index > 0
+                    && self.erasables.get(index - 1) == Some(&Erasable::ConversionArrow) =>
+            {
+                self.remove_erasable_at_cursor_position()
+                    .map_err(|e| ParsingError::Custom(e.to_string()))?;
+                self.record_edit(Edit::Other);
+                self.insert_at_cursor_position(Erasable::ShiftRight);
+                Ok(())
+            }
+            _ => self.add_at_cursor_position(c).map(|_| ()),
+        }
+    }
+
+    /// Moves the cursor to the next argument of a function, or out of its
+    /// brackets after the last one (Tab). Returns false if there's nowhere to go.
+    pub fn move_cursor_to_next_slot(&mut self) -> bool {
+        let index = self.get_cursor_position(CursorPositionUnit::ErasableCount);
+
+        let slot_end = self.erasables[index..].iter().position(|e| {
+            matches!(
+                ErasableType::from(e),
+                ErasableType::Comma | ErasableType::ClosingBracket
+            )
+        });
+
+        match slot_end {
+            Some(offset) => {
+                self.move_cursor_to(index + offset + 1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Inserts pasted text at the cursor, reading it as in the tokenize_in
+    /// function. Either all of it is inserted or, if any characters aren't
+    /// recognised, none of it is.
+    pub fn paste_at_cursor_position(
+        &mut self,
+        text: &str,
+        locale: Locale,
+    ) -> Result<(), ParsingError> {
+        // pasting a line often brings its line break along too
+        let index = self.get_cursor_position(CursorPositionUnit::ErasableCount);
+        let erasables = tokenize_after(text.trim(), &self.erasables[..index], locale)?;
+        self.paste_erasables_at_cursor_position(&erasables);
+
+        Ok(())
+    }
+
+    /// Inserts erasables (eg: from the clipboard) at the cursor, in place of
+    /// the selection if there is one.
+    pub fn paste_erasables_at_cursor_position(&mut self, erasables: &[Erasable]) {
+        let selection = self.selection();
+
+        self.record_edit(Edit::Other);
+        self.word = None;
+
+        if let Some(selection) = selection {
+            self.drain(selection);
+        }
+
+        for e in erasables {
+            self.insert_at_cursor_position(e.clone());
+        }
+    }
+
+    fn insert_at_cursor_position(&mut self, e: Erasable) -> &Erasable {
         match &(self.cursor.position) {
             CursorPosition::Empty | CursorPosition::Start => {
                 let index = 0;
                 self.cursor.move_toward(Sign::Positive);
                 self.erasables.insert(index, e);
-                Ok(&self.erasables[index])
+                &self.erasables[index]
             }
             CursorPosition::NotEmpty(position) => {
-                let index = if let None = self.erasables.get(*position) {
+                let index = if self.erasables.get(*position).is_none() {
                     *position
                 } else {
                     (*position) + 1
@@ -267,7 +760,7 @@ impl ErasableCluster {
 
                 self.cursor.move_toward(Sign::Positive);
                 self.erasables.insert(index, e);
-                Ok(&self.erasables[index])
+                &self.erasables[index]
             }
         }
     }
@@ -275,12 +768,58 @@ impl ErasableCluster {
     /// Removes the element the cursor points to. If there are no erasables
     /// or if the cursor is at the start, an error is returned.
     pub fn remove_at_cursor_position(&mut self) -> Result<Erasable, MutationOperationError> {
+        let before = self.snapshot();
+        self.remove_and_record(before)
+    }
+
+    // removes the element before the cursor, with undoing it going back to
+    // `before`
+    fn remove_and_record(&mut self, before: Snapshot) -> Result<Erasable, MutationOperationError> {
+        self.word = None;
+        let e = self.remove_erasable_at_cursor_position()?;
+
+        self.undo_stack.push(before);
+        self.redo_stack.clear();
+        self.last_edit = Some(Edit::Other);
+        self.selection_anchor = None;
+
+        Ok(e)
+    }
+
+    /// Removes the element after the cursor (Delete), leaving the cursor
+    /// where it is. If the cursor is at the end, an error is returned.
+    pub fn remove_after_cursor_position(&mut self) -> Result<Erasable, MutationOperationError> {
+        let index = self.get_cursor_position(CursorPositionUnit::ErasableCount);
+
+        if index >= self.erasables.len() {
+            return Err(MutationOperationError::RemovalError);
+        }
+
+        // undoing it puts the cursor back before the element, not after it
+        let before = self.snapshot();
+
+        self.move_cursor_to(index + 1);
+        self.remove_and_record(before)
+    }
+
+    /// Removes every erasable (Esc). This can be undone like any other edit.
+    pub fn clear(&mut self) {
+        if self.erasables.is_empty() {
+            return;
+        }
+
+        self.record_edit(Edit::Other);
+        self.erasables.clear();
+        self.move_cursor_to(0);
+    }
+
+    fn remove_erasable_at_cursor_position(&mut self) -> Result<Erasable, MutationOperationError> {
         match &(self.cursor.position) {
             CursorPosition::Empty | CursorPosition::Start => {
                 Err(MutationOperationError::RemovalError)
             }
             CursorPosition::NotEmpty(position) => {
-                if self.erasables.len() == 0 {
+                if self.erasables.is_empty() {
                     return Err(MutationOperationError::RemovalError);
                 }
 
@@ -293,7 +832,62 @@ impl ErasableCluster {
         }
     }
 
-    pub fn iter(&self) -> Iter<Erasable> {
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            erasables: self.erasables.clone(),
+            cursor: self.cursor.position.clone(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.erasables = snapshot.erasables;
+        self.cursor.position = snapshot.cursor;
+        self.word = None;
+        self.last_edit = None;
+        self.selection_anchor = None;
+    }
+
+    // to be called just before changing the erasables
+    fn record_edit(&mut self, edit: Edit) {
+        let continues_number =
+            edit == Edit::NumberInput && self.last_edit == Some(Edit::NumberInput);
+
+        if !continues_number {
+            self.undo_stack.push(self.snapshot());
+            self.redo_stack.clear();
+        }
+
+        self.last_edit = Some(edit);
+        self.selection_anchor = None;
+    }
+
+    /// Reverts the last edit, putting the cursor back where it was before it.
+    /// Returns false if there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.undo_stack.pop() else {
+            return false;
+        };
+
+        self.redo_stack.push(self.snapshot());
+        self.restore(snapshot);
+
+        true
+    }
+
+    /// Reapplies the last undone edit. Returns false if there was nothing to
+    /// redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(snapshot) = self.redo_stack.pop() else {
+            return false;
+        };
+
+        self.undo_stack.push(self.snapshot());
+        self.restore(snapshot);
+
+        true
+    }
+
+    pub fn iter(&self) -> Iter<'_, Erasable> {
         self.erasables.iter()
     }
 
@@ -311,14 +905,10 @@ impl ErasableCluster {
 //     }
 // }
 
-impl ToString for ErasableCluster {
-    fn to_string(&self) -> String {
-        let mut result = String::with_capacity(self.erasables.len());
-        for e in &self.erasables {
-            result.push_str(e.into())
-        }
-
-        result
+// as it's kept in the history, so it reads back the same
+impl std::fmt::Display for ErasableCluster {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&spell(&self.erasables))
     }
 }
 
@@ -329,7 +919,7 @@ mod tests {
     #[test]
     fn parse_into_erasables() {
         let cluster = ErasableCluster::build("120 + 23").unwrap();
-        let expected = vec![
+        let expected = [
             Erasable::One,
             Erasable::Two,
             Erasable::Zero,
@@ -340,11 +930,7 @@ mod tests {
             Erasable::Three,
         ];
 
-        assert_eq!(cluster.erasables.len(), expected.len());
-
-        for i in 0..expected.len() {
-            assert_eq!(cluster.erasables[i], expected[i]);
-        }
+        assert_eq!(cluster.erasables, expected);
     }
 
     #[test]
@@ -437,6 +1023,442 @@ mod tests {
         }
     }
 
+    #[test]
+    fn building_from_full_names_works() {
+        let cluster = ErasableCluster::build("sin(30)").unwrap();
+        assert_eq!(cluster.to_string(), "sin(30)");
+        assert_eq!(cluster.get_cursor_position(CursorPositionUnit::Chars), 7);
+        assert_eq!(
+            cluster.get_cursor_position(CursorPositionUnit::ErasableCount),
+            5
+        );
+    }
+
+    #[test]
+    fn typing_full_names_works() {
+        let mut cluster = ErasableCluster::new();
+
+        for c in "2asin(1)+xdeg".chars() {
+            cluster.type_at_cursor_position(c).unwrap();
+        }
+
+        assert_eq!(cluster.to_string(), "2asin(1)+xdeg");
+        assert_eq!(
+            cluster.erasables,
+            ErasableCluster::build("2S(1)+xd").unwrap().erasables
+        );
+
+        // moving the cursor starts a new word
+        cluster.move_cursor_to_prev_erasable();
+        cluster.type_at_cursor_position('p').unwrap();
+        cluster.type_at_cursor_position('i').unwrap();
+        assert_eq!(cluster.to_string(), "2asin(1)+xpideg");
+        assert_eq!(cluster.get_cursor_position(CursorPositionUnit::Chars), 12);
+    }
+
+    #[test]
+    fn typing_names_that_start_with_other_names_works() {
+        // eg: `ta` is read as tan and abs, which mustn't stop `tan` being typed
+        for input in ["tan(0)", "atan(1)", "1rad", "2 grad", "1 day"] {
+            let mut cluster = ErasableCluster::new();
+
+            for c in input.chars() {
+                cluster.type_at_cursor_position(c).unwrap();
+            }
+
+            assert_eq!(cluster.to_string(), input);
+            assert_eq!(
+                cluster.erasables,
+                ErasableCluster::build(input).unwrap().erasables
+            );
+        }
+    }
+
+    #[test]
+    fn the_text_of_a_cluster_reads_back_the_same() {
+        let typed = |input: &str| {
+            let mut cluster = ErasableCluster::new();
+
+            for c in input.chars() {
+                cluster.type_at_cursor_position(c).unwrap();
+            }
+
+            cluster
+        };
+
+        for input in [
+            "2 A",
+            "2 ans",
+            "5km/h",
+            "10 degC/s",
+            "3 mi in km",
+            "2 s(30d)",
+        ] {
+            let cluster = typed(input);
+
+            assert_eq!(
+                ErasableCluster::build(&cluster.to_string())
+                    .unwrap()
+                    .erasables,
+                cluster.erasables
+            );
+        }
+
+        // metres then i, which would be read as miles run together
+        let mut cluster = typed("5i");
+        cluster.move_cursor_to_prev_erasable();
+        cluster.type_at_cursor_position('m').unwrap();
+        assert_eq!(
+            cluster.erasables,
+            [Erasable::Five, Erasable::Metre, Erasable::VariableI]
+        );
+        assert_eq!(cluster.to_string(), "5m i");
+        assert_eq!(
+            ErasableCluster::build("5m i").unwrap().erasables,
+            [
+                Erasable::Five,
+                Erasable::Metre,
+                Erasable::Space,
+                Erasable::VariableI
+            ]
+        );
+    }
+
+    #[test]
+    fn letters_that_carry_on_a_name_are_told_apart() {
+        let mut cluster = ErasableCluster::new();
+
+        cluster.type_at_cursor_position('s').unwrap();
+        assert!(cluster.continues_word('q'));
+        assert!(!cluster.continues_word('x'));
+
+        for c in "x+Nt".chars() {
+            cluster.type_at_cursor_position(c).unwrap();
+        }
+        assert!(cluster.continues_word('h'));
+
+        // a new word can't be carried on yet
+        cluster.move_cursor_to_prev_erasable();
+        assert!(!cluster.continues_word('h'));
+
+        // but a unit can be started after a quantity, spaced or not
+        let cluster = ErasableCluster::build("5 ").unwrap();
+        assert!(cluster.continues_word('h'));
+        assert!(cluster.continues_word('k'));
+        assert!(!cluster.continues_word('q'));
+        assert!(!ErasableCluster::build("5+").unwrap().continues_word('h'));
+    }
+
+    #[test]
+    fn units_typed_after_a_quantity_stay_units() {
+        let mut cluster = ErasableCluster::new();
+
+        for c in "2 s(30d)".chars() {
+            cluster.type_at_cursor_position(c).unwrap();
+        }
+        assert_eq!(cluster.to_string(), "2 s(30deg)");
+        assert_eq!(cluster.erasables[2], Erasable::Second);
+
+        let mut cluster = ErasableCluster::new();
+
+        for c in "5km/h".chars() {
+            cluster.type_at_cursor_position(c).unwrap();
+        }
+        assert_eq!(
+            cluster.erasables,
+            [
+                Erasable::Five,
+                Erasable::Kilo,
+                Erasable::Metre,
+                Erasable::DivisionSign,
+                Erasable::Hour
+            ]
+        );
+    }
+
+    #[test]
+    fn greater_than_twice_is_a_right_shift() {
+        let mut cluster = ErasableCluster::new();
+
+        for c in "16>>2".chars() {
+            cluster.type_at_cursor_position(c).unwrap();
+        }
+
+        assert_eq!(cluster.to_string(), "16>>2");
+        assert_eq!(cluster.erasables[2], Erasable::ShiftRight);
+        assert_eq!(cluster.erasables.len(), 4);
+    }
+
+    #[test]
+    fn functions_and_brackets_are_closed_automatically() {
+        let mut cluster = ErasableCluster::new();
+
+        cluster.type_at_cursor_position('s').unwrap();
+        assert_eq!(cluster.to_string(), "sin()");
+        assert_eq!(cluster.get_cursor_position(CursorPositionUnit::Chars), 4);
+
+        // letters that can't carry on the name go inside the brackets
+        for c in "x+[2".chars() {
+            cluster.type_at_cursor_position(c).unwrap();
+        }
+        assert_eq!(cluster.to_string(), "sin(x+[2])");
+
+        // existing closing brackets are stepped over
+        cluster.type_at_cursor_position(']').unwrap();
+        cluster.type_at_cursor_position(')').unwrap();
+        assert_eq!(cluster.to_string(), "sin(x+[2])");
+        assert!(cluster.is_cursor_at_end());
+
+        cluster.type_at_cursor_position(')').unwrap();
+        assert_eq!(cluster.to_string(), "sin(x+[2]))");
+    }
+
+    #[test]
+    fn tab_moves_between_arguments() {
+        let mut cluster = ErasableCluster::build("2").unwrap();
+
+        cluster.type_at_cursor_position('R').unwrap();
+        assert_eq!(cluster.to_string(), "2NthRoot(,)");
+
+        cluster.type_at_cursor_position('3').unwrap();
+        assert!(cluster.move_cursor_to_next_slot());
+        cluster.type_at_cursor_position('8').unwrap();
+        assert!(cluster.move_cursor_to_next_slot());
+        cluster.type_at_cursor_position('+').unwrap();
+
+        assert_eq!(cluster.to_string(), "2NthRoot(3,8)+");
+        assert!(!cluster.move_cursor_to_next_slot());
+
+        // the whole template goes in one undo step
+        cluster.undo();
+        cluster.undo();
+        cluster.undo();
+        assert_eq!(cluster.to_string(), "2NthRoot(,)");
+        cluster.undo();
+        assert_eq!(cluster.to_string(), "2");
+    }
+
+    #[test]
+    fn typing_over_a_template_works() {
+        let mut cluster = ErasableCluster::new();
+
+        for c in "NthRoot(2,8)".chars() {
+            cluster.type_at_cursor_position(c).unwrap();
+        }
+
+        assert_eq!(cluster.to_string(), "NthRoot(2,8)");
+        assert!(cluster.is_cursor_at_end());
+    }
+
+    #[test]
+    fn pasting_inserts_everything_or_nothing() {
+        let mut cluster = ErasableCluster::build("2()").unwrap();
+        cluster.move_cursor_to_prev_erasable();
+
+        cluster
+            .paste_at_cursor_position("sqrt(8) + pi\n", Locale::default())
+            .unwrap();
+        assert_eq!(cluster.to_string(), "2(NthRoot(2,8) + pi)");
+
+        let error = cluster
+            .paste_at_cursor_position("1 + q #", Locale::default())
+            .unwrap_err();
+        assert_eq!(error.to_string(), "error: unknown characters: 'q', '#'");
+        assert_eq!(cluster.to_string(), "2(NthRoot(2,8) + pi)");
+    }
+
+    #[test]
+    fn undoing_and_redoing_edits_works() {
+        let mut cluster = ErasableCluster::new();
+
+        for c in "12.5+x".chars() {
+            cluster.add_at_cursor_position(c).unwrap();
+        }
+
+        cluster.move_cursor_to_prev_erasable();
+        cluster.remove_at_cursor_position().unwrap();
+        assert_eq!(cluster.to_string(), "12.5x");
+
+        // the removal, with the cursor back after the plus sign
+        assert!(cluster.undo());
+        assert_eq!(cluster.to_string(), "12.5+x");
+        assert_eq!(cluster.get_cursor_position(CursorPositionUnit::Chars), 5);
+
+        // x, then +, then the whole number at once
+        assert!(cluster.undo());
+        assert!(cluster.undo());
+        assert_eq!(cluster.to_string(), "12.5");
+        assert!(cluster.undo());
+        assert!(cluster.is_empty());
+        assert!(!cluster.undo());
+
+        assert!(cluster.redo());
+        assert!(cluster.redo());
+        assert_eq!(cluster.to_string(), "12.5+");
+        assert_eq!(cluster.get_cursor_position(CursorPositionUnit::Chars), 5);
+
+        // a new edit replaces what could have been redone
+        cluster.add_at_cursor_position('3').unwrap();
+        assert!(!cluster.redo());
+        assert_eq!(cluster.to_string(), "12.5+3");
+    }
+
+    #[test]
+    fn moving_the_cursor_separates_numbers_when_undoing() {
+        let mut cluster = ErasableCluster::build("12").unwrap();
+
+        cluster.move_cursor_to_prev_erasable();
+        cluster.add_at_cursor_position('3').unwrap();
+        cluster.move_cursor_to_next_erasable();
+        cluster.add_at_cursor_position('4').unwrap();
+        assert_eq!(cluster.to_string(), "1324");
+
+        cluster.undo();
+        assert_eq!(cluster.to_string(), "132");
+        assert_eq!(cluster.get_cursor_position(CursorPositionUnit::Chars), 3);
+    }
+
+    #[test]
+    fn home_end_and_delete_work() {
+        let mut cluster = ErasableCluster::build("1 + 2").unwrap();
+
+        cluster.move_cursor_to_start();
+        assert_eq!(cluster.get_cursor_position(CursorPositionUnit::Chars), 0);
+
+        cluster.remove_after_cursor_position().unwrap();
+        cluster.remove_after_cursor_position().unwrap();
+        assert_eq!(cluster.to_string(), "+ 2");
+        assert_eq!(cluster.get_cursor_position(CursorPositionUnit::Chars), 0);
+
+        cluster.move_cursor_to_end();
+        assert_eq!(cluster.get_cursor_position(CursorPositionUnit::Chars), 3);
+        assert!(cluster.remove_after_cursor_position().is_err());
+
+        // undoing a Delete leaves the cursor where it was
+        cluster.move_cursor_to_start();
+        cluster.remove_after_cursor_position().unwrap();
+        assert!(cluster.undo());
+        assert_eq!(cluster.to_string(), "+ 2");
+        assert_eq!(cluster.get_cursor_position(CursorPositionUnit::Chars), 0);
+    }
+
+    #[test]
+    fn jumping_by_token_works() {
+        let mut cluster = ErasableCluster::build("12.5 * sin(30deg)").unwrap();
+        let mut positions = vec![];
+
+        cluster.move_cursor_to_start();
+        for _ in 0..7 {
+            cluster.move_cursor_to_next_token();
+            positions.push(cluster.get_cursor_position(CursorPositionUnit::Chars));
+        }
+
+        // 12.5| *| sin(|30|deg|)| and then nowhere further
+        assert_eq!(positions, [4, 6, 11, 13, 16, 17, 17]);
+
+        positions.clear();
+        for _ in 0..7 {
+            cluster.move_cursor_to_prev_token();
+            positions.push(cluster.get_cursor_position(CursorPositionUnit::Chars));
+        }
+
+        assert_eq!(positions, [16, 13, 11, 7, 5, 0, 0]);
+    }
+
+    #[test]
+    fn clearing_works_and_can_be_undone() {
+        let mut cluster = ErasableCluster::build("1 + 2").unwrap();
+
+        cluster.clear();
+        assert!(cluster.is_empty());
+        assert_eq!(cluster.get_cursor_position(CursorPositionUnit::Chars), 0);
+
+        cluster.add_at_cursor_position('3').unwrap();
+        cluster.undo();
+        cluster.undo();
+        assert_eq!(cluster.to_string(), "1 + 2");
+    }
+
+    #[test]
+    fn selecting_works_in_both_directions() {
+        let mut cluster = ErasableCluster::build("1 + 23").unwrap();
+        assert_eq!(cluster.selection(), None);
+
+        cluster.select_prev_erasable();
+        cluster.select_prev_erasable();
+        assert_eq!(cluster.selection(), Some(4..6));
+
+        // going back over the anchor selects the other side of it
+        cluster.move_cursor_to_start();
+        cluster.select_next_erasable();
+        assert_eq!(cluster.selection(), Some(0..1));
+        cluster.select_to_end();
+        assert_eq!(cluster.selection(), Some(0..6));
+
+        cluster.move_cursor_to_next_erasable();
+        assert_eq!(cluster.selection(), None);
+    }
+
+    #[test]
+    fn cutting_copying_and_pasting_works() {
+        let mut cluster = ErasableCluster::build("1 + 23").unwrap();
+
+        cluster.select_prev_erasable();
+        cluster.select_prev_erasable();
+        let copied = cluster.copy_selection().unwrap();
+        assert_eq!(copied, [Erasable::Two, Erasable::Three]);
+
+        cluster.move_cursor_to_start();
+        cluster.paste_erasables_at_cursor_position(&copied);
+        assert_eq!(cluster.to_string(), "231 + 23");
+
+        cluster.select_to_end();
+        let cut = cluster.cut_selection().unwrap();
+        assert_eq!(cluster.to_string(), "23");
+        assert_eq!(cut.len(), 6);
+
+        // pasting replaces the selection
+        cluster.select_to_start();
+        cluster.paste_erasables_at_cursor_position(&cut);
+        assert_eq!(cluster.to_string(), "1 + 23");
+
+        cluster.undo();
+        assert_eq!(cluster.to_string(), "23");
+    }
+
+    #[test]
+    fn typing_replaces_or_wraps_the_selection() {
+        let mut cluster = ErasableCluster::build("1 + 2 * 3").unwrap();
+
+        cluster.move_cursor_to_start();
+        for _ in 0..5 {
+            cluster.select_next_erasable();
+        }
+
+        cluster.add_at_cursor_position('[').unwrap();
+        assert_eq!(cluster.to_string(), "[1 + 2] * 3");
+        assert_eq!(cluster.get_cursor_position(CursorPositionUnit::Chars), 7);
+
+        cluster.select_to_end();
+        cluster.type_at_cursor_position('x').unwrap();
+        assert_eq!(cluster.to_string(), "[1 + 2]x");
+
+        cluster.select_prev_erasable();
+        assert!(cluster.remove_selection());
+        assert_eq!(cluster.to_string(), "[1 + 2]");
+    }
+
+    #[test]
+    fn brackets_are_matched() {
+        let cluster = ErasableCluster::build("(1 + [2))) * (3").unwrap();
+
+        assert_eq!(cluster.matching_bracket(0), Some(8));
+        assert_eq!(cluster.matching_bracket(8), Some(0));
+        assert_eq!(cluster.matching_bracket(5), Some(7));
+        assert_eq!(cluster.matching_bracket(1), None);
+        assert_eq!(cluster.unmatched_brackets(), [9, 13]);
+    }
+
     #[test]
     fn displaying_a_cluster_works() {
         let cluster = ErasableCluster::build("s(30d)").unwrap();
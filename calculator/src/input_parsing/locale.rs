@@ -0,0 +1,87 @@
+// which characters separate a number's decimals and a function's arguments,
+// eg: 3.14 and NthRoot(2, 8), or 3,14 and NthRoot(2; 8) with a decimal comma.
+// Erasables (and the input history) always use the standard ones, so only
+// what's typed, pasted and shown changes
+
+use super::erasable::Erasable;
+
+/// The separators input is typed and shown with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Locale {
+    /// eg: 3.14 and NthRoot(2, 8)
+    #[default]
+    DecimalPoint,
+    /// eg: 3,14 and NthRoot(2; 8)
+    DecimalComma,
+}
+
+impl Locale {
+    /// eg: the . of 3.14
+    pub fn decimal_separator(self) -> char {
+        match self {
+            Locale::DecimalPoint => '.',
+            Locale::DecimalComma => ',',
+        }
+    }
+
+    /// eg: the , of NthRoot(2, 8)
+    pub fn argument_separator(self) -> char {
+        match self {
+            Locale::DecimalPoint => ',',
+            Locale::DecimalComma => ';',
+        }
+    }
+
+    /// The character as it's written with the standard separators, eg: ; is
+    /// , with a decimal comma.
+    pub fn standard(self, c: char) -> char {
+        if self == Locale::DecimalPoint {
+            c
+        } else if c == self.decimal_separator() {
+            '.'
+        } else if c == self.argument_separator() {
+            ','
+        } else {
+            c
+        }
+    }
+
+    /// The standard character as it's written in the locale, eg: , is ;
+    /// with a decimal comma.
+    pub fn localised(self, c: char) -> char {
+        match c {
+            '.' => self.decimal_separator(),
+            ',' => self.argument_separator(),
+            c => c,
+        }
+    }
+
+    /// How the erasable is shown, eg: , for the decimal point with a decimal
+    /// comma.
+    pub fn text_of(self, erasable: &Erasable) -> String {
+        match erasable {
+            Erasable::DecimalPoint => self.decimal_separator().to_string(),
+            Erasable::Comma => self.argument_separator().to_string(),
+            erasable => erasable.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separators_are_swapped_with_a_decimal_comma() {
+        let standard = |text: &str| -> String {
+            text.chars()
+                .map(|c| Locale::DecimalComma.standard(c))
+                .collect()
+        };
+
+        assert_eq!(standard("NthRoot(2; 12,5) + 1.5"), "NthRoot(2, 12.5) + 1.5");
+        assert_eq!(Locale::DecimalPoint.standard(';'), ';');
+        assert_eq!(Locale::DecimalComma.text_of(&Erasable::Comma), ";");
+        assert_eq!(Locale::DecimalComma.text_of(&Erasable::Sin), "sin");
+    }
+}
@@ -1,2 +1,4 @@
 pub mod erasable;
 pub mod erasable_cluster;
+pub mod locale;
+pub mod tokenizer;
@@ -0,0 +1,542 @@
+// Turns text into erasables, accepting the full names of erasables (eg: sin,
+// pi, deg) as well as their one-letter codes (eg: s, p, d).
+//
+// Names are matched greedily, so `asin` is arcsine rather than abs and sin.
+//
+// Units (eg: km) are names too, but many could also be read as one-letter
+// codes (eg: km as the variables k and m), so those are only units where a
+// unit can go: after a quantity, which is a number or a closing bracket (eg:
+// 5 km, 5km or (2 m)^(2) m), after another unit and / or * (eg: the s of m/s),
+// or after ->. There a unit is always a unit, with or without a space before
+// it, so 2 s(30d) is 2 seconds times 30 degrees and 2 A is 2 amperes (sin and
+// ans are typed by name there, eg: 2 sin(30d) and 2 ans). `in` (as in 5 km in
+// m) is read as -> by the same rule, so i and n can still be variables
+// elsewhere.
+//
+// Integers in other bases (eg: 0x1F) are read by where they are too: an x, b
+// or o straight after a 0 that starts a number begins one, and in a
+// hexadecimal one, the letters A to F (or a to f) are digits.
+//
+// Text can be written with the separators of a locale (see Locale), which are
+// read as the standard ones.
+
+use num_traits::ToPrimitive;
+use strum::IntoEnumIterator;
+
+use super::{
+    erasable::{Erasable, ErasableType},
+    locale::Locale,
+};
+use crate::{
+    calculation::units::{prefix_factor, Unit},
+    shared::errors::ParsingError,
+};
+
+// other names people commonly use, which don't have an erasable of their own
+const ALIASES: [(&str, &[Erasable]); 8] = [
+    (
+        "sqrt(",
+        &[
+            Erasable::NthRoot,
+            Erasable::LeftParenthesis,
+            Erasable::Two,
+            Erasable::Comma,
+        ],
+    ),
+    (
+        "cbrt(",
+        &[
+            Erasable::NthRoot,
+            Erasable::LeftParenthesis,
+            Erasable::Three,
+            Erasable::Comma,
+        ],
+    ),
+    ("arcsin", &[Erasable::Arcsin]),
+    ("arccos", &[Erasable::Arccos]),
+    ("arctan", &[Erasable::Arctan]),
+    ("π", &[Erasable::Pi]),
+    // ° is hard to type
+    ("degC", &[Erasable::Celsius]),
+    ("degF", &[Erasable::Fahrenheit]),
+];
+
+struct Keyword {
+    name: String,
+    erasables: Vec<Erasable>,
+    place: UnitPlace,
+}
+
+// where a name can be read, from anywhere to the fewest places
+#[derive(PartialEq, PartialOrd, Clone, Copy)]
+enum UnitPlace {
+    Anywhere,
+    // a unit that could be read as one-letter codes, eg: km or m
+    AfterAQuantity,
+}
+
+// every name that isn't just the erasable's one-letter code (eg: sin, or °),
+// with what it stands for
+fn keywords() -> Vec<Keyword> {
+    let names = Erasable::iter()
+        .filter(|erasable| {
+            !matches!(
+                ErasableType::from(erasable),
+                ErasableType::Unit | ErasableType::BasePrefix | ErasableType::HexDigit
+            )
+        })
+        .map(|erasable| (<&str>::from(&erasable), erasable))
+        .filter(|(name, erasable)| {
+            let code = erasable.to_u8().map(|code| code as char);
+            name.chars().ne(code)
+        })
+        .map(|(name, erasable)| (name.to_string(), vec![erasable]));
+
+    let aliases = ALIASES
+        .iter()
+        .map(|(name, erasables)| (name.to_string(), erasables.to_vec()));
+
+    names
+        .chain(aliases)
+        .map(|(name, erasables)| Keyword {
+            name,
+            erasables,
+            place: UnitPlace::Anywhere,
+        })
+        .chain(unit_keywords())
+        .chain([Keyword {
+            name: "in".to_string(),
+            erasables: vec![Erasable::ConversionArrow],
+            place: UnitPlace::AfterAQuantity,
+        }])
+        .collect()
+}
+
+// every unit, on its own and with each prefix it can take (eg: m and km)
+fn unit_keywords() -> Vec<Keyword> {
+    let units: Vec<Erasable> = Erasable::iter()
+        .filter(|erasable| ErasableType::from(erasable) == ErasableType::Unit)
+        .collect();
+
+    let mut keywords = vec![];
+
+    for unit in units
+        .iter()
+        .filter(|unit| Unit::build(None, unit).is_some())
+    {
+        keywords.push(vec![unit.clone()]);
+
+        for prefix in &units {
+            if Unit::build(Some(prefix), unit).is_some() {
+                keywords.push(vec![prefix.clone(), unit.clone()]);
+            }
+        }
+    }
+
+    keywords
+        .into_iter()
+        .map(|erasables| {
+            let name: String = erasables
+                .iter()
+                .map(|erasable| erasable.to_string())
+                .collect();
+
+            (name, erasables)
+        })
+        .map(|(name, erasables)| {
+            let place = if name.chars().all(|c| Erasable::build(c).is_ok()) {
+                UnitPlace::AfterAQuantity
+            } else {
+                UnitPlace::Anywhere
+            };
+
+            Keyword {
+                name,
+                erasables,
+                place,
+            }
+        })
+        .collect()
+}
+
+// which units can go after the erasables
+fn unit_place_after(erasables: &[Erasable]) -> UnitPlace {
+    let mut before = erasables
+        .iter()
+        .rev()
+        .filter(|erasable| ErasableType::from(*erasable) != ErasableType::Formatting);
+
+    let is_unit = |erasable: Option<&Erasable>| {
+        erasable.is_some_and(|erasable| {
+            matches!(
+                ErasableType::from(erasable),
+                ErasableType::Unit | ErasableType::AngleUnit
+            )
+        })
+    };
+
+    let last = before.next();
+
+    let is_after_a_quantity = match last {
+        Some(Erasable::MultiplicationSign | Erasable::DivisionSign) => is_unit(before.next()),
+        last => {
+            is_unit(last)
+                || last.is_some_and(|erasable| {
+                    matches!(
+                        ErasableType::from(erasable),
+                        ErasableType::Digit
+                            | ErasableType::HexDigit
+                            | ErasableType::DecimalPoint
+                            | ErasableType::ClosingBracket
+                            | ErasableType::ConversionOperator
+                    )
+                })
+        }
+    };
+
+    match is_after_a_quantity {
+        true => UnitPlace::AfterAQuantity,
+        false => UnitPlace::Anywhere,
+    }
+}
+
+// what `c` is in an integer in another base (eg: the x or the F of 0x1F),
+// if it goes on one after the erasables
+fn integer_in_base_part(erasables: &[Erasable], c: char) -> Option<Erasable> {
+    use Erasable::*;
+
+    let digits = erasables
+        .iter()
+        .rev()
+        .take_while(|erasable| {
+            matches!(
+                ErasableType::from(*erasable),
+                ErasableType::Digit | ErasableType::HexDigit
+            )
+        })
+        .count();
+
+    let (before, digits) = erasables.split_at(erasables.len() - digits);
+
+    match (before.last(), digits) {
+        (Some(HexadecimalPrefix), _) => match c.to_ascii_uppercase() {
+            'A' => Some(HexDigitA),
+            'B' => Some(HexDigitB),
+            'C' => Some(HexDigitC),
+            'D' => Some(HexDigitD),
+            'E' => Some(HexDigitE),
+            'F' => Some(HexDigitF),
+            _ => None,
+        },
+        // not eg: 1.0x or 0b0x
+        (before, [Zero])
+            if before.is_none_or(|erasable| {
+                !matches!(
+                    ErasableType::from(erasable),
+                    ErasableType::DecimalPoint | ErasableType::BasePrefix
+                )
+            }) =>
+        {
+            match c {
+                'x' => Some(HexadecimalPrefix),
+                'b' => Some(BinaryPrefix),
+                'o' => Some(OctalPrefix),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Whether `text` is a name or the start of one, eg: `as` (of `asin`) or
+/// `tan`.
+pub(crate) fn is_start_of_name(text: &str) -> bool {
+    keywords()
+        .iter()
+        .any(|keyword| keyword.name.starts_with(text))
+}
+
+/// Whether `text` is the start of a unit that can go straight after the
+/// erasables, eg: `h` (of hours) after `5 `.
+pub(crate) fn is_start_of_unit_after(text: &str, before: &[Erasable]) -> bool {
+    unit_place_after(before) == UnitPlace::AfterAQuantity
+        && unit_keywords()
+            .iter()
+            .any(|keyword| keyword.name.starts_with(text))
+}
+
+/// Converts `input` into erasables. Keywords like `sin` become a single
+/// erasable, and any other character is read as a one-letter code.
+///
+/// If any characters aren't recognised, all of them are listed in the error.
+pub fn tokenize(input: &str) -> Result<Vec<Erasable>, ParsingError> {
+    tokenize_in(input, Locale::default())
+}
+
+/// The erasables written out so that `tokenize` reads them back the same,
+/// with a space put in where two names would otherwise run together, eg: the
+/// metres and i of `5m i`, which would be miles as `5mi`.
+pub(crate) fn spell(erasables: &[Erasable]) -> String {
+    let read = |text: &str| tokenize(text).unwrap_or_default();
+    let mut text = String::new();
+    let mut rest = erasables;
+
+    while let [first, ..] = rest {
+        // a prefix goes with its unit, eg: km
+        let length = match prefix_factor(first) {
+            Some(_) => rest.len().min(2),
+            None => 1,
+        };
+
+        let (piece, after) = rest.split_at(length);
+        rest = after;
+
+        let piece_text: String = piece.iter().map(|erasable| erasable.to_string()).collect();
+
+        let mut expected = read(&text);
+        expected.extend_from_slice(piece);
+
+        if read(&format!("{text}{piece_text}")) != expected {
+            text.push(' ');
+        }
+
+        text.push_str(&piece_text);
+    }
+
+    text
+}
+
+/// Like `tokenize`, for text written with the locale's separators, eg: 3,14
+/// with a decimal comma.
+pub fn tokenize_in(input: &str, locale: Locale) -> Result<Vec<Erasable>, ParsingError> {
+    tokenize_after(input, &[], locale)
+}
+
+/// Like `tokenize`, for text that goes after the erasables (eg: typed after
+/// them), which decide whether a unit can go at its start.
+pub(crate) fn tokenize_after(
+    input: &str,
+    before: &[Erasable],
+    locale: Locale,
+) -> Result<Vec<Erasable>, ParsingError> {
+    let keywords = keywords();
+    let mut erasables = before.to_vec();
+    let mut unknown_characters = vec![];
+    let input: String = input.chars().map(|c| locale.standard(c)).collect();
+    let mut rest = input.as_str();
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(erasable) = integer_in_base_part(&erasables, c) {
+            erasables.push(erasable);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+
+        let unit_place = unit_place_after(&erasables);
+
+        let longest_match = keywords
+            .iter()
+            .filter(|keyword| keyword.place <= unit_place)
+            .filter(|keyword| rest.starts_with(&keyword.name))
+            .max_by_key(|keyword| keyword.name.len());
+
+        match longest_match {
+            Some(keyword) => {
+                erasables.extend(keyword.erasables.iter().cloned());
+                rest = &rest[keyword.name.len()..];
+            }
+            None => {
+                match Erasable::build(c) {
+                    Ok(erasable) => erasables.push(erasable),
+                    Err(_) => {
+                        if !unknown_characters.contains(&c) {
+                            unknown_characters.push(c);
+                        }
+                    }
+                }
+
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    if unknown_characters.is_empty() {
+        Ok(erasables.split_off(before.len()))
+    } else {
+        Err(ParsingError::UnknownCharacters(unknown_characters))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_and_codes_can_be_mixed() {
+        let expected = [
+            Erasable::Sin,
+            Erasable::LeftParenthesis,
+            Erasable::Three,
+            Erasable::Zero,
+            Erasable::Degrees,
+            Erasable::RightParenthesis,
+            Erasable::PlusSign,
+            Erasable::Cos,
+            Erasable::Pi,
+        ];
+
+        assert_eq!(tokenize("sin(30deg)+cpi").unwrap(), expected);
+        assert_eq!(tokenize("s(30d)+cp").unwrap(), expected);
+    }
+
+    #[test]
+    fn the_longest_name_wins() {
+        assert_eq!(tokenize("asin").unwrap(), [Erasable::Arcsin]);
+        assert_eq!(
+            tokenize("absx").unwrap(),
+            [Erasable::Absolute, Erasable::VariableX]
+        );
+        assert_eq!(tokenize("sine").unwrap(), [Erasable::Sin, Erasable::E]);
+        assert_eq!(
+            tokenize("1°2grad").unwrap(),
+            [
+                Erasable::One,
+                Erasable::DegreeSign,
+                Erasable::Two,
+                Erasable::Gradians
+            ]
+        );
+    }
+
+    #[test]
+    fn aliases_are_expanded() {
+        assert_eq!(
+            tokenize("sqrt(9)").unwrap(),
+            [
+                Erasable::NthRoot,
+                Erasable::LeftParenthesis,
+                Erasable::Two,
+                Erasable::Comma,
+                Erasable::Nine,
+                Erasable::RightParenthesis,
+            ]
+        );
+        assert_eq!(tokenize("2π").unwrap(), [Erasable::Two, Erasable::Pi]);
+    }
+
+    #[test]
+    fn units_need_a_quantity() {
+        use Erasable::*;
+
+        assert_eq!(
+            tokenize("5 km/h").unwrap(),
+            [Five, Space, Kilo, Metre, DivisionSign, Hour]
+        );
+        // names that could be codes are only units after a quantity
+        assert_eq!(tokenize("km").unwrap(), [VariableK, VariableM]);
+        assert_eq!(tokenize("1->min").unwrap(), [One, ConversionArrow, Minute]);
+        // even one-letter ones, with or without a space
+        assert_eq!(tokenize("5 m").unwrap(), [Five, Space, Metre]);
+        assert_eq!(tokenize("5m").unwrap(), [Five, Metre]);
+        assert_eq!(tokenize("2 A").unwrap(), [Two, Space, Ampere]);
+        assert_eq!(
+            tokenize("2 s(1)").unwrap(),
+            [Two, Space, Second, LeftParenthesis, One, RightParenthesis]
+        );
+        assert_eq!(
+            tokenize("1 V/A").unwrap(),
+            [One, Space, Volt, DivisionSign, Ampere]
+        );
+        // and after a closing bracket
+        assert_eq!(
+            tokenize("(2)m").unwrap(),
+            [LeftParenthesis, Two, RightParenthesis, Metre]
+        );
+        // names that aren't units are still read there
+        assert_eq!(tokenize("2 ans").unwrap(), [Two, Space, Answer]);
+        assert_eq!(tokenize("2sin").unwrap(), [Two, Sin]);
+        // but not after other operators
+        assert_eq!(
+            tokenize("5 m - m").unwrap(),
+            [Five, Space, Metre, Space, NegativeSign, Space, VariableM]
+        );
+        assert_eq!(tokenize("x m").unwrap(), [VariableX, Space, VariableM]);
+        // as is in
+        assert_eq!(
+            tokenize("30d in rad").unwrap(),
+            [Three, Zero, Degrees, Space, ConversionArrow, Space, Radians]
+        );
+        assert_eq!(tokenize("in").unwrap(), [VariableI, VariableN]);
+    }
+
+    #[test]
+    fn integers_can_be_in_other_bases() {
+        use Erasable::*;
+
+        assert_eq!(
+            tokenize("0xbeef").unwrap(),
+            [
+                Zero,
+                HexadecimalPrefix,
+                HexDigitB,
+                HexDigitE,
+                HexDigitE,
+                HexDigitF
+            ]
+        );
+        assert_eq!(tokenize("0b10").unwrap(), [Zero, BinaryPrefix, One, Zero]);
+        assert_eq!(tokenize("0o7").unwrap(), [Zero, OctalPrefix, Seven]);
+        // only after a 0 that starts a number
+        assert_eq!(tokenize("10x").unwrap(), [One, Zero, VariableX]);
+        assert_eq!(
+            tokenize("0b0x").unwrap(),
+            [Zero, BinaryPrefix, Zero, VariableX]
+        );
+    }
+
+    #[test]
+    fn bitwise_operators_can_be_typed_by_name() {
+        use Erasable::*;
+
+        assert_eq!(
+            tokenize("not6and3xor1or2").unwrap(),
+            [BitwiseNot, Six, BitwiseAnd, Three, BitwiseXor, One, BitwiseOr, Two]
+        );
+        assert_eq!(
+            tokenize("1<<2>>x").unwrap(),
+            [One, ShiftLeft, Two, ShiftRight, VariableX]
+        );
+        assert_eq!(tokenize("5->m").unwrap()[1], ConversionArrow);
+    }
+
+    #[test]
+    fn separators_can_be_those_of_a_locale() {
+        use Erasable::*;
+
+        let expected = [
+            NthRoot,
+            LeftParenthesis,
+            Two,
+            Comma,
+            One,
+            DecimalPoint,
+            Five,
+            RightParenthesis,
+        ];
+
+        assert_eq!(tokenize("NthRoot(2,1.5)").unwrap(), expected);
+        assert_eq!(
+            tokenize_in("NthRoot(2;1,5)", Locale::DecimalComma).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn unknown_characters_are_rejected() {
+        assert!(tokenize("√4").is_err());
+        assert_eq!(
+            tokenize("sin(q) + #(q)").unwrap_err().to_string(),
+            "error: unknown characters: 'q', '#'"
+        );
+    }
+}
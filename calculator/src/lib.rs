@@ -1,5 +1,11 @@
 pub mod calculation;
+pub mod config;
+// not hooked up to the terminal output yet
+#[allow(dead_code)]
 mod display;
+pub mod help;
+pub mod highlighting;
+pub mod input_history;
 pub mod input_parsing;
 mod shared;
 
@@ -10,19 +16,7 @@ use std::{fmt::Display, io::stdout};
 
 use crossterm::{execute, style::Print};
 
-use std::fs::File;
-use std::io::{self, prelude::*, stderr, BufReader};
-
-pub fn display_help_text() -> io::Result<()> {
-    let file = File::open("help_text.txt")?;
-    let reader = BufReader::new(file);
-
-    for line in reader.lines() {
-        println(&(line?))?;
-    }
-
-    Ok(())
-}
+use std::io::{self, prelude::*, stderr};
 
 pub struct OnScreenCursorCoordinates {
     pub from_left: u16,
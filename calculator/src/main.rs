@@ -1,11 +1,24 @@
 use calculator::{
-    calculation::calculator::Calculator, display_help_text, eprint,
-    input_parsing::erasable_cluster::ErasableCluster, print, println,
+    calculation::{
+        calculator::{Calculator, OutputSettings, WordSize},
+        environment::Environment,
+    },
+    config::{Action, Config, KeyBindings},
+    eprint,
+    help::{help_text, show_help},
+    highlighting::{highlight, Theme},
+    input_history::InputHistory,
+    input_parsing::{
+        erasable::Erasable,
+        erasable_cluster::{CursorPositionUnit, ErasableCluster},
+    },
+    print, println,
 };
 use crossterm::{
     cursor,
-    event::{read, Event, KeyCode},
+    event::{read, Event, KeyCode, KeyModifiers},
     execute, queue,
+    style::Stylize,
     terminal::{self, disable_raw_mode, enable_raw_mode},
 };
 use std::{
@@ -15,33 +28,221 @@ use std::{
 
 // const BACKSPACE: char = 8u8 as char;
 
-fn rerender(output: &str, root_position: (u16, u16)) -> Result<(), std::io::Error> {
+fn rerender(
+    output: &str,
+    preview: &str,
+    root_position: &mut (u16, u16),
+) -> Result<(), std::io::Error> {
     let mut stdout = stdout();
 
     disable_raw_mode()?;
 
+    // make room for the preview under the input at the bottom of the screen
+    if !preview.is_empty() && root_position.1 + 1 >= terminal::size()?.1 {
+        queue!(stdout, terminal::ScrollUp(1))?;
+        root_position.1 = root_position.1.saturating_sub(1);
+    }
+
     queue!(stdout, cursor::MoveTo(root_position.0, root_position.1))?;
     queue!(stdout, terminal::Clear(terminal::ClearType::FromCursorDown))?;
 
     stdout.flush()?;
 
-    print(&output)?;
+    print(output)?;
+
+    if !preview.is_empty() {
+        print(format!("\n{}", preview.dim()))?;
+    }
+
     enable_raw_mode()?;
 
     Ok(())
 }
 
+// removes the preview from under the input, before showing anything else there
+fn clear_preview(root_position: (u16, u16)) -> std::io::Result<()> {
+    execute!(
+        stdout(),
+        cursor::SavePosition,
+        cursor::MoveTo(0, root_position.1 + 1),
+        terminal::Clear(terminal::ClearType::FromCursorDown),
+        cursor::RestorePosition,
+    )
+}
+
+// what the input would give if Enter were pressed now, or why it can't be
+// worked out yet
+fn preview(
+    cluster: &ErasableCluster,
+    environment: &Environment,
+    output_settings: &OutputSettings,
+) -> String {
+    if cluster.is_empty() {
+        return String::new();
+    }
+
+    match Calculator::build_with_environment(cluster, environment) {
+        // nothing to show, as when Enter is pressed
+        Ok(calc) if calc.is_function_definition() => String::new(),
+        Ok(calc) => match calc
+            .with_output_settings(output_settings.clone())
+            .next_output_mode()
+        {
+            Ok(value) => format!("= {value}"),
+            Err(e) => e.to_string(),
+        },
+        Err(e) => e.to_string(),
+    }
+}
+
+// also puts copied text on the terminal's clipboard (as it's shown), where
+// supported
+#[cfg(feature = "osc52")]
+fn copy_to_terminal_clipboard(
+    erasables: &[Erasable],
+    locale: calculator::input_parsing::locale::Locale,
+) -> std::io::Result<()> {
+    use base64::Engine;
+
+    let text: String = erasables.iter().map(|e| locale.text_of(e)).collect();
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+
+    print(format!("\x1b]52;c;{encoded}\x07"))
+}
+
+// moves the terminal cursor to where the cluster's cursor is
+fn move_to_cursor(cluster: &ErasableCluster, root_position: (u16, u16)) -> std::io::Result<()> {
+    let chars = cluster.get_cursor_position(CursorPositionUnit::Chars);
+
+    execute!(
+        stdout(),
+        cursor::MoveTo(root_position.0 + chars as u16, root_position.1),
+        cursor::SavePosition,
+    )
+}
+
+// what pressing the key does. A letter that carries on the name being typed
+// (eg: the q of sqrt) or starts a unit after a quantity (eg: the h of 5 h) is
+// typed, whatever its key does otherwise
+fn action_of(c: char, cluster: &ErasableCluster, key_bindings: &KeyBindings) -> Action {
+    if cluster.continues_word(c) {
+        Action::Type(c)
+    } else {
+        key_bindings.action(c)
+    }
+}
+
+// Ctrl-R: looks for earlier input as the search is typed, with Ctrl-R again
+// going further back. Returns the entry chosen with Enter (or None on Esc).
+fn reverse_search(
+    history: &InputHistory,
+    mut root_position: (u16, u16),
+) -> Result<Option<String>, std::io::Error> {
+    let mut query = String::new();
+    let mut found: Option<(usize, String)> = None;
+
+    loop {
+        let shown = found.as_ref().map_or("", |(_, entry)| entry.as_str());
+        rerender(
+            &format!("(search) {query}: {shown}"),
+            "",
+            &mut root_position,
+        )?;
+
+        if let Event::Key(event) = read()? {
+            match event.code {
+                KeyCode::Char('r') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    let before = found.as_ref().map(|(index, _)| *index);
+
+                    if let Some((index, entry)) = history.search(&query, before) {
+                        found = Some((index, entry.to_string()));
+                    }
+                }
+                KeyCode::Char(c) => {
+                    query.push(c);
+                    found = history
+                        .search(&query, None)
+                        .map(|(index, entry)| (index, entry.to_string()));
+                }
+                KeyCode::Backspace => {
+                    query.pop();
+                    found = if query.is_empty() {
+                        None
+                    } else {
+                        history
+                            .search(&query, None)
+                            .map(|(index, entry)| (index, entry.to_string()))
+                    };
+                }
+                KeyCode::Enter => return Ok(found.map(|(_, entry)| entry)),
+                KeyCode::Esc => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
+
 fn main() -> Result<(), std::io::Error> {
+    // a mistake in the config is pointed out rather than half ignored
+    let config = match Config::default_file() {
+        Some(file) => Config::load(&file).unwrap_or_else(|e| {
+            eprintln!("{e} ({})", file.display());
+            process::exit(1);
+        }),
+        None => Config::default(),
+    };
+    let Config {
+        locale,
+        angle_unit,
+        word_size,
+        mut output_settings,
+        theme,
+        key_bindings,
+    } = config;
+
+    if std::env::args().skip(1).any(|arg| arg == "--help") {
+        print!("{}", help_text(&key_bindings));
+        return Ok(());
+    }
+
     let mut cluster = ErasableCluster::new();
+    // what was last cut or copied
+    let mut clipboard: Vec<Erasable> = Vec::new();
+    // the last result shown, which Tab shows in other output modes
+    let mut last_calculation: Option<Calculator> = None;
+    let mut environment = Environment::new();
+    environment.set_angle_unit(angle_unit);
+    environment.set_word_size(word_size);
+    let theme = theme.with_env().unwrap_or_else(|e| {
+        let _ = eprint(format!("couldn't read CALCULATOR_COLORS, {e}"));
+        Theme::default()
+    });
     let mut output;
 
+    let mut history = match InputHistory::default_file() {
+        Some(file) => InputHistory::load(&file).unwrap_or_else(|e| {
+            let _ = eprint(format!("couldn't load the input history: {e}"));
+            InputHistory::new()
+        }),
+        None => InputHistory::new(),
+    };
+
     println("The calculator you never knew you needed (until you started calculus).")?;
-    println("For help, press h. To quit, press q.")?;
+    println(format!(
+        "For help, press {}. To quit, press {}.",
+        key_bindings.key(Action::Help).unwrap_or('h'),
+        key_bindings.key(Action::Quit).unwrap_or('q'),
+    ))?;
 
     let mut root_position = cursor::position()?;
 
     execute!(stdout(), cursor::SavePosition)?;
 
+    // so that pasted text arrives all at once, rather than as key presses
+    // (which would quit on q, for example)
+    #[cfg(feature = "bracketed-paste")]
+    execute!(stdout(), crossterm::event::EnableBracketedPaste)?;
+
     enable_raw_mode()?;
 
     loop {
@@ -52,119 +253,327 @@ fn main() -> Result<(), std::io::Error> {
             // Event::FocusLost => println!("FocusLost"),
             // Event::Resize(width, height) => println!("New size {}x{}", width, height),
             Event::Key(event) => match event.code {
-                KeyCode::Char(c) => match c {
-                    'q' => {
+                KeyCode::Char('r') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    if let Some(entry) = reverse_search(&history, root_position)? {
+                        cluster = ErasableCluster::build(&entry).unwrap_or_default();
+                    }
+
+                    move_to_cursor(&cluster, root_position)?;
+                    true
+                }
+                KeyCode::Char('z') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    cluster.undo();
+                    move_to_cursor(&cluster, root_position)?;
+                    true
+                }
+                KeyCode::Char('y') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    cluster.redo();
+                    move_to_cursor(&cluster, root_position)?;
+                    true
+                }
+                KeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    if let Some(copied) = cluster.copy_selection() {
+                        #[cfg(feature = "osc52")]
+                        copy_to_terminal_clipboard(&copied, locale)?;
+                        clipboard = copied;
+                    }
+                    false
+                }
+                KeyCode::Char('x') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    match cluster.cut_selection() {
+                        Some(cut) => {
+                            #[cfg(feature = "osc52")]
+                            copy_to_terminal_clipboard(&cut, locale)?;
+                            clipboard = cut;
+
+                            move_to_cursor(&cluster, root_position)?;
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                KeyCode::Char('v') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    if clipboard.is_empty() {
+                        false
+                    } else {
+                        cluster.paste_erasables_at_cursor_position(&clipboard);
+                        move_to_cursor(&cluster, root_position)?;
+                        true
+                    }
+                }
+                KeyCode::Char(c) => match action_of(c, &cluster, &key_bindings) {
+                    Action::Quit => {
+                        clear_preview(root_position)?;
                         println("")?;
                         println("See ya later!")?;
+                        #[cfg(feature = "bracketed-paste")]
+                        execute!(stdout(), crossterm::event::DisableBracketedPaste)?;
                         disable_raw_mode()?;
                         process::exit(0);
                     }
-                    'h' => {
-                        if let Err(_) = display_help_text() {
-                            eprint("unable to display help text")?;
+                    Action::Help => match show_help(&key_bindings) {
+                        // the help has a screen of its own, so the input is as it was
+                        Ok(()) => true,
+                        Err(e) => {
+                            last_calculation = None;
+                            clear_preview(root_position)?;
+                            eprint(format!("unable to display help text: {e}"))?;
+                            root_position = cursor::position()?;
+                            true
                         }
+                    },
+                    Action::NextAngleUnit => {
+                        let angle_unit = environment.angle_unit().next();
+                        environment.set_angle_unit(angle_unit);
+
+                        // what's typed so far is shown again under the message
+                        last_calculation = None;
+                        clear_preview(root_position)?;
+                        println("")?;
+                        println(format!("Angles are now in {angle_unit}."))?;
+                        println("")?;
+
                         root_position = cursor::position()?;
-                        false
+                        true
                     }
-                    _ => match cluster.add_at_cursor_position(c) {
-                        Ok(e) => {
-                            execute!(
-                                stdout(),
-                                cursor::MoveRight(e.length_in_chars() as u16),
-                                cursor::SavePosition,
-                            )?;
+                    Action::NextNotation => {
+                        let number_format = &mut output_settings.number_format;
+                        number_format.notation = number_format.notation.next();
+
+                        last_calculation = None;
+                        clear_preview(root_position)?;
+                        println("")?;
+                        println(format!(
+                            "Decimals are now written in {} notation.",
+                            number_format.notation
+                        ))?;
+                        println("")?;
+
+                        root_position = cursor::position()?;
+                        true
+                    }
+                    Action::NextWordSize => {
+                        let word_size = WordSize::next(environment.word_size());
+                        environment.set_word_size(word_size);
+
+                        last_calculation = None;
+                        clear_preview(root_position)?;
+                        println("")?;
+                        match word_size {
+                            Some(word_size) => println(format!(
+                                "Whole numbers are now {word_size}s (programmer mode)."
+                            ))?,
+                            None => println("Programmer mode is off.")?,
+                        }
+                        println("")?;
+
+                        root_position = cursor::position()?;
+                        true
+                    }
+                    Action::Type(c) => match cluster.type_at_cursor_position(c) {
+                        Ok(()) => {
+                            // typing can turn several letters into one name, so
+                            // the cursor isn't always just one character along
+                            move_to_cursor(&cluster, root_position)?;
                             true
                         }
                         Err(_) => {
-                            eprint(&format!("unknown character: {}", c))?;
+                            // the last result isn't just above the input any more
+                            last_calculation = None;
+                            clear_preview(root_position)?;
+                            eprint(format!("unknown character: {}", c))?;
 
                             root_position = cursor::position()?;
                             true
                         }
                     },
                 },
+                KeyCode::Backspace | KeyCode::Delete if cluster.remove_selection() => {
+                    move_to_cursor(&cluster, root_position)?;
+                    true
+                }
                 KeyCode::Backspace => {
                     if cluster.is_empty() {
                         false
                     } else {
-                        match cluster.remove_at_cursor_position() {
-                            Ok(e) => execute!(
+                        if let Ok(e) = cluster.remove_at_cursor_position() {
+                            execute!(
                                 stdout(),
                                 cursor::MoveLeft(e.length_in_chars() as u16),
                                 cursor::SavePosition,
-                            )?,
-                            Err(_) => (),
+                            )?
                         }
 
                         true
                     }
                 }
-                KeyCode::Left => {
-                    match cluster.move_cursor_to_prev_erasable() {
-                        Some(e) => execute!(
+                // the cursor stays where it is
+                KeyCode::Delete => cluster.remove_after_cursor_position().is_ok(),
+                KeyCode::Home if event.modifiers.contains(KeyModifiers::SHIFT) => {
+                    cluster.select_to_start();
+                    move_to_cursor(&cluster, root_position)?;
+                    true
+                }
+                KeyCode::End if event.modifiers.contains(KeyModifiers::SHIFT) => {
+                    cluster.select_to_end();
+                    move_to_cursor(&cluster, root_position)?;
+                    true
+                }
+                KeyCode::Left if event.modifiers.contains(KeyModifiers::SHIFT) => {
+                    cluster.select_prev_erasable();
+                    move_to_cursor(&cluster, root_position)?;
+                    true
+                }
+                KeyCode::Right if event.modifiers.contains(KeyModifiers::SHIFT) => {
+                    cluster.select_next_erasable();
+                    move_to_cursor(&cluster, root_position)?;
+                    true
+                }
+                // moving the cursor rerenders too, for the bracket highlight
+                KeyCode::Home => {
+                    cluster.move_cursor_to_start();
+                    move_to_cursor(&cluster, root_position)?;
+                    true
+                }
+                KeyCode::End => {
+                    cluster.move_cursor_to_end();
+                    move_to_cursor(&cluster, root_position)?;
+                    true
+                }
+                KeyCode::Left if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    cluster.move_cursor_to_prev_token();
+                    move_to_cursor(&cluster, root_position)?;
+                    true
+                }
+                KeyCode::Right if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    cluster.move_cursor_to_next_token();
+                    move_to_cursor(&cluster, root_position)?;
+                    true
+                }
+                // shows the last result in its next output mode, in place
+                // (eg: 7/2 becomes 3 1/2, then 3.5)
+                KeyCode::Tab if cluster.is_empty() => {
+                    if let Some(Ok(value)) = last_calculation.as_mut().map(|c| c.next_output_mode())
+                    {
+                        execute!(
                             stdout(),
-                            cursor::MoveLeft(e.length_in_chars() as u16),
-                            cursor::SavePosition,
-                        )?,
-                        None => (),
-                    };
+                            cursor::MoveTo(0, root_position.1.saturating_sub(2)),
+                            terminal::Clear(terminal::ClearType::CurrentLine),
+                        )?;
+                        print(value)?;
+                        move_to_cursor(&cluster, root_position)?;
+                    }
                     false
                 }
+                KeyCode::Tab => cluster.move_cursor_to_next_slot(),
+                KeyCode::Esc => {
+                    cluster.clear();
+                    move_to_cursor(&cluster, root_position)?;
+                    true
+                }
+                KeyCode::Left => {
+                    cluster.move_cursor_to_prev_erasable();
+                    true
+                }
                 KeyCode::Right => {
-                    match cluster.move_cursor_to_next_erasable() {
-                        Some(e) => {
-                            execute!(
-                                stdout(),
-                                cursor::MoveRight(e.length_in_chars() as u16),
-                                cursor::SavePosition,
-                            )?;
-                        }
-                        None => (),
-                    };
-                    false
+                    cluster.move_cursor_to_next_erasable();
+                    true
                 }
-                KeyCode::Enter => {
-                    if !cluster.is_empty() {
-                        let calc = Calculator::build(&cluster);
-                        cluster = ErasableCluster::new();
+                KeyCode::Up => match history.older(&cluster.to_string()) {
+                    Some(entry) => {
+                        cluster = ErasableCluster::build(entry).unwrap_or_default();
+                        move_to_cursor(&cluster, root_position)?;
+                        true
+                    }
+                    None => false,
+                },
+                KeyCode::Down => match history.newer() {
+                    Some(entry) => {
+                        // back past the latest entry is what was being typed,
+                        // which might be nothing
+                        cluster = ErasableCluster::build(entry).unwrap_or_default();
+                        move_to_cursor(&cluster, root_position)?;
+                        true
+                    }
+                    None => false,
+                },
+                KeyCode::Enter if !cluster.is_empty() => {
+                    clear_preview(root_position)?;
+                    let calc = Calculator::build_with_environment(&cluster, &environment)
+                        .map(|calc| calc.with_output_settings(output_settings.clone()));
 
-                        match calc {
-                            Ok(mut calc) => {
-                                let result = calc.next_inexact_output_mode();
+                    if let Err(e) = history.push(cluster.to_string()) {
+                        eprint(format!("couldn't save the input history: {e}"))?;
+                    }
+
+                    cluster = ErasableCluster::new();
 
-                                match result {
+                    last_calculation = None;
+
+                    match calc {
+                        Ok(mut calc) => {
+                            let is_shown = if calc.is_function_definition() {
+                                // nothing to show, it's just usable from now on
+                                println("")?;
+                                false
+                            } else {
+                                // the exact result comes first (eg: √2/2 rather than 0.7071...)
+                                match calc.next_output_mode() {
                                     Ok(value) => {
-                                        if value.is_nan() {
-                                            eprint("math error")?;
-                                        } else {
-                                            println("")?;
-                                            println(value)?;
-                                            println("")?;
-                                        }
+                                        println("")?;
+                                        println(value)?;
+                                        println("")?;
+                                        true
+                                    }
+                                    Err(err) => {
+                                        eprint(err)?;
+                                        false
                                     }
-                                    Err(err) => eprint(err)?,
                                 }
+                            };
+
+                            // any error here has already been shown with the
+                            // result. Tab rewrites the result's line, so there
+                            // must be one
+                            if calc.store_result(&mut environment).is_ok() && is_shown {
+                                last_calculation = Some(calc);
                             }
-                            Err(e) => eprint(e)?,
                         }
-
-                        root_position = cursor::position()?;
-                        false
-                    } else {
-                        false
+                        Err(e) => eprint(e)?,
                     }
+
+                    root_position = cursor::position()?;
+                    false
                 }
                 _ => false,
             },
             #[cfg(feature = "bracketed-paste")]
-            Event::Paste(data) => println!("{:?}", data),
+            Event::Paste(data) => match cluster.paste_at_cursor_position(&data, locale) {
+                Ok(()) => {
+                    move_to_cursor(&cluster, root_position)?;
+                    true
+                }
+                Err(e) => {
+                    last_calculation = None;
+                    clear_preview(root_position)?;
+                    eprint(format!("couldn't paste that, {e}"))?;
+
+                    root_position = cursor::position()?;
+                    true
+                }
+            },
             _ => false,
         };
 
         if do_trigger_a_rerender {
-            output = cluster.to_string();
-            rerender(&output, root_position)?;
-            execute!(stdout(), cursor::RestorePosition)?;
+            output = highlight(&cluster, &theme, locale);
+            rerender(
+                &output,
+                &preview(&cluster, &environment, &output_settings),
+                &mut root_position,
+            )?;
+            // the input may have moved up to make room for the preview
+            move_to_cursor(&cluster, root_position)?;
         }
     }
 }
@@ -24,6 +24,7 @@ pub enum ParsingError {
     EndOfInput,
     Unexpected(String),
     ExcessiveDecimalPoints,
+    UnknownCharacters(Vec<char>),
     Custom(String),
 }
 
@@ -42,6 +43,10 @@ impl Display for ParsingError {
                 "only one decimal point is allowed in a decimal".to_string()
             }
             ParsingError::Unexpected(x) => format!("unexpected {}", x),
+            ParsingError::UnknownCharacters(characters) => {
+                let characters: Vec<String> = characters.iter().map(|c| format!("{c:?}")).collect();
+                format!("unknown characters: {}", characters.join(", "))
+            }
             ParsingError::Custom(s) => s.to_string(),
         };
         let msg = format!("error: {msg}");
@@ -89,3 +94,20 @@ impl Display for CalculationError {
         Display::fmt(&msg, f)
     }
 }
+
+#[derive(Debug)]
+pub struct ConfigError(String);
+
+impl ConfigError {
+    pub fn new(msg: String) -> Self {
+        Self(msg)
+    }
+}
+
+impl Error for ConfigError {}
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = format!("error in the config file: {}", self.0);
+        Display::fmt(&msg, f)
+    }
+}
@@ -1,6 +1,6 @@
 use std::ops::{Neg, Not};
 
-#[derive(PartialEq, Debug, Clone, Copy, Default)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
 pub enum Sign {
     #[default]
     Positive = 1,
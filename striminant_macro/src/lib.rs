@@ -156,7 +156,7 @@ pub fn striminant(attr_args: TokenStream, item: TokenStream) -> TokenStream {
                                 _ => panic!("unable to parse discriminant value; value must be a byte, char or integer literal"),
                             };
 
-                            last_discriminant = get_value_from_literal(&discriminant);
+                            last_discriminant = get_value_from_literal(discriminant);
 
                             if config.exceptions().contains(&last_discriminant) {
                                 panic!(
@@ -56,56 +56,51 @@ pub fn parse_attribute_arguments(args: TokenStream) -> Config {
     let mut output_type: Option<OutputType> = None;
 
     while let Some(token) = iterator.next() {
-        match token {
-            // argument name (all the arguments must be named)
-            Ident(ident) => {
-                let ident = ident.to_string();
-                match &ident[..] {
-                    "except" => {
-                        // equal sign; ignore it
-                        iterator.next().unwrap();
-
-                        let exceptions = iterator.next().unwrap();
-                        let mut exceptions: Vec<u8> = if let Group(exceptions) = exceptions {
-                            exceptions
-                                .stream()
-                                .into_iter()
-                                .filter_map(|exception| {
-                                    match exception {
-                                        Literal(lit) => Some(parse_literal(lit.to_string())),
-                                        Punct(_) => None,
-                                        _ => panic!(
-                                            "Unknown value for 'except' in striminant macro attribute: {}", 
-                                            exception
-                                        )
-                                    }
-                                })
-                                .collect()
-                        } else {
-                            panic!(
+        // argument name (all the arguments must be named)
+        if let Ident(ident) = token {
+            let ident = ident.to_string();
+            match &ident[..] {
+                "except" => {
+                    // equal sign; ignore it
+                    iterator.next().unwrap();
+
+                    let exceptions = iterator.next().unwrap();
+                    let mut exceptions: Vec<u8> = if let Group(exceptions) = exceptions {
+                        exceptions
+                            .stream()
+                            .into_iter()
+                            .filter_map(|exception| match exception {
+                                Literal(lit) => Some(parse_literal(lit.to_string())),
+                                Punct(_) => None,
+                                _ => panic!(
+                                    "Unknown value for 'except' in striminant macro attribute: {}",
+                                    exception
+                                ),
+                            })
+                            .collect()
+                    } else {
+                        panic!(
                                 "expected array for the 'except' in striminant macro attribute, found {}", 
                                 exceptions
                             )
-                        };
-
-                        all_exceptions.append(&mut exceptions);
-                    }
-                    "output" => {
-                        // equal sign; ignore it
-                        iterator.next().unwrap();
-
-                        let output_type_str = iterator.next().unwrap().to_string();
-
-                        output_type = match &output_type_str[..] {
-                            "\"num\"" => Some(OutputType::Num),
-                            "\"char\"" => Some(OutputType::Char),
-                            _ => panic!("Unknown output type: {output_type_str}"),
-                        };
-                    }
-                    _ => panic!("invalid argument to striminant macro: {}", ident),
+                    };
+
+                    all_exceptions.append(&mut exceptions);
+                }
+                "output" => {
+                    // equal sign; ignore it
+                    iterator.next().unwrap();
+
+                    let output_type_str = iterator.next().unwrap().to_string();
+
+                    output_type = match &output_type_str[..] {
+                        "\"num\"" => Some(OutputType::Num),
+                        "\"char\"" => Some(OutputType::Char),
+                        _ => panic!("Unknown output type: {output_type_str}"),
+                    };
                 }
+                _ => panic!("invalid argument to striminant macro: {}", ident),
             }
-            _ => {}
         }
     }
 